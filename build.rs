@@ -0,0 +1,38 @@
+// kanjiban
+// (C) 2025 by JoAn
+// With the "embedded-levels" feature, compiles every file of levels/ into
+// the binary, so it can run without the level files next to it.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo::rerun-if-changed=levels");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_LEVELS").is_none() {
+        return;
+    }
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let levels_dir = Path::new(&manifest_dir).join("levels");
+    let mut files: Vec<_> = fs::read_dir(&levels_dir)
+        .expect("levels directory not found")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    let mut code = String::from("pub static EMBEDDED_FILES: &[(&str, &str)] = &[\n");
+    for path in files {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        code += &format!(
+            "    ({:?}, include_str!({:?})),\n",
+            format!("levels/{name}"),
+            path.display().to_string()
+        );
+    }
+    code += "];\n";
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(Path::new(&out_dir).join("embedded_levels.rs"), code)
+        .expect("can't write embedded_levels.rs");
+}
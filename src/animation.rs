@@ -0,0 +1,182 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Tweened movement of the player and pushed boxes. The animation only looks at
+// the game states it is shown, so the game logic doesn't know about it and
+// drawing never holds back the next move.
+
+use macroquad::prelude::*;
+
+use crate::game_logic::{GameCell, GameState, Point};
+
+// how long a box that landed on a sink pulses
+const SETTLE_DURATION: f64 = 0.25;
+
+#[derive(Clone, Copy)]
+struct Tween {
+    // position (in cells) the movement started at
+    from: Vec2,
+    to: Point,
+    started: f64,
+    duration: f64,
+}
+
+impl Tween {
+    fn still(at: Point) -> Self {
+        Self {
+            from: cell_vec(&at),
+            to: at,
+            started: f64::NEG_INFINITY,
+            duration: 0.,
+        }
+    }
+
+    fn progress(&self, now: f64) -> f32 {
+        if self.duration <= 0. {
+            return 1.;
+        }
+        ((now - self.started) / self.duration).clamp(0., 1.) as f32
+    }
+
+    fn position(&self, now: f64) -> Vec2 {
+        let t = self.progress(now);
+        // ease in and out
+        let t = t * t * (3. - 2. * t);
+        self.from.lerp(cell_vec(&self.to), t)
+    }
+}
+
+fn cell_vec(p: &Point) -> Vec2 {
+    vec2(p.x as f32, p.y as f32)
+}
+
+pub struct BoardAnimation {
+    // seconds a step takes; changes apply to the next step
+    duration: f64,
+    previous: Option<GameState>,
+    player: Tween,
+    pushed_box: Option<Tween>,
+    // box that landed on a sink, and when it got there
+    settle: Option<(Point, f64)>,
+}
+
+impl BoardAnimation {
+    pub fn new(duration: f64) -> Self {
+        Self {
+            duration,
+            previous: None,
+            player: Tween::still(Point { x: 0, y: 0 }),
+            pushed_box: None,
+            settle: None,
+        }
+    }
+
+    pub fn set_duration(&mut self, duration: f64) {
+        self.duration = duration;
+    }
+
+    // Take note of the state about to be drawn. Single steps (also undone ones)
+    // are animated; any other change, like loading a level, is shown at once.
+    pub fn update(&mut self, state: &GameState, now: f64) {
+        if self.previous.as_ref() == Some(state) {
+            return;
+        }
+        // the previous state, if the player did a single step since
+        let stepped_from = self.previous.as_ref().filter(|previous| {
+            let step = state.get_player_position() - previous.get_player_position();
+            previous.width() == state.width()
+                && previous.height() == state.height()
+                && step.x.abs() + step.y.abs() == 1
+        });
+        match stepped_from {
+            Some(previous) => {
+                let moved_box = moved_box(previous, state);
+                self.player = Tween {
+                    from: self.player.position(now),
+                    to: state.get_player_position(),
+                    started: now,
+                    duration: self.duration,
+                };
+                self.pushed_box = moved_box.map(|(from, to)| Tween {
+                    from: self.box_position(&from, now),
+                    to,
+                    started: now,
+                    duration: self.duration,
+                });
+                self.settle = moved_box
+                    .filter(|(_, to)| state.get_cell(to) == GameCell::SinkWithBox)
+                    .map(|(_, to)| (to, now + self.duration));
+            }
+            None => {
+                self.player = Tween::still(state.get_player_position());
+                self.pushed_box = None;
+                self.settle = None;
+            }
+        }
+        self.previous = Some(state.clone());
+    }
+
+    // true while something is still moving
+    pub fn is_running(&self, now: f64) -> bool {
+        self.player.progress(now) < 1.
+            || self
+                .settle
+                .is_some_and(|(_, landed)| now < landed + SETTLE_DURATION)
+    }
+
+    // Position of the player in cells; fractional while moving.
+    pub fn player_position(&self, state: &GameState, now: f64) -> Vec2 {
+        if self.previous.is_none() {
+            return cell_vec(&state.get_player_position());
+        }
+        self.player.position(now)
+    }
+
+    // Position of the box on the given cell in cells; fractional while moving.
+    pub fn box_position(&self, cell: &Point, now: f64) -> Vec2 {
+        match &self.pushed_box {
+            Some(tween) if tween.to == *cell => tween.position(now),
+            _ => cell_vec(cell),
+        }
+    }
+
+    // true iff the box on the given cell hasn't arrived yet
+    pub fn is_box_moving(&self, cell: &Point, now: f64) -> bool {
+        self.pushed_box
+            .is_some_and(|tween| tween.to == *cell && tween.progress(now) < 1.)
+    }
+
+    // Size factor of the box on the given cell; boxes that just landed on a
+    // sink grow a bit and settle back.
+    pub fn box_scale(&self, cell: &Point, now: f64) -> f32 {
+        match self.settle {
+            Some((at, landed)) if at == *cell && now >= landed => {
+                let t = ((now - landed) / SETTLE_DURATION).min(1.) as f32;
+                1. + 0.15 * (t * std::f32::consts::PI).sin()
+            }
+            _ => 1.,
+        }
+    }
+}
+
+// The box that moved between two states, as cell it left and cell it entered.
+fn moved_box(previous: &GameState, state: &GameState) -> Option<(Point, Point)> {
+    let mut left = Vec::new();
+    let mut entered = Vec::new();
+    for y in 0..state.height() {
+        for x in 0..state.width() {
+            let pos = Point { x, y };
+            match (
+                previous.is_occupied_by_box(&pos),
+                state.is_occupied_by_box(&pos),
+            ) {
+                (true, false) => left.push(pos),
+                (false, true) => entered.push(pos),
+                _ => {}
+            }
+        }
+    }
+    match (left.as_slice(), entered.as_slice()) {
+        ([from], [to]) => Some((*from, *to)),
+        _ => None,
+    }
+}
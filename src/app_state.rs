@@ -0,0 +1,210 @@
+// kanjiban
+// (C) 2025 by JoAn
+// The screens of the application (title menu, level select, playing, ...) on a
+// stack. Only the top screen is updated; it answers with the transition to
+// make, like pushing a pause menu or playing the next level. Everything the
+// screens share lives in the AppContext.
+
+use macroquad::prelude::*;
+
+use crate::game_board::GameBoard;
+use crate::input_control::{Command, InputControl};
+use crate::level_chooser::LevelChooser;
+use crate::level_list::{LevelEntry, score_text};
+use crate::macroquad_helpers::FontProvider;
+use crate::play_screen::PlayScreen;
+use crate::save_data::SaveData;
+
+// choices for the settings, as (label, seconds per step)
+pub const ANIMATION_SPEEDS: [(&str, f64); 3] = [("Normal", 0.15), ("Fast", 0.08), ("Off", 0.)];
+pub const WALK_SPEEDS: [(&str, f64); 3] = [("Normal", 0.06), ("Fast", 0.03), ("Slow", 0.12)];
+
+pub struct Settings {
+    // indices into ANIMATION_SPEEDS and WALK_SPEEDS
+    pub animation: usize,
+    pub walk: usize,
+    // go on to the next level a few seconds after solving one
+    pub auto_advance: bool,
+}
+
+impl Settings {
+    fn load(save_data: &SaveData) -> Self {
+        let choice = |name, choices: &[(&str, f64)]| {
+            save_data
+                .setting(name)
+                .and_then(|value| choices.iter().position(|(label, _)| *label == value))
+                .unwrap_or(0)
+        };
+        Self {
+            animation: choice("animation", &ANIMATION_SPEEDS),
+            walk: choice("walk", &WALK_SPEEDS),
+            auto_advance: save_data.setting("auto_advance") == Some("on"),
+        }
+    }
+
+    pub fn store(&self, save_data: &mut SaveData) {
+        save_data.set_setting("animation", ANIMATION_SPEEDS[self.animation].0.to_owned());
+        save_data.set_setting("walk", WALK_SPEEDS[self.walk].0.to_owned());
+        let auto_advance = if self.auto_advance { "on" } else { "off" };
+        save_data.set_setting("auto_advance", auto_advance.to_owned());
+    }
+
+    // seconds a single step is animated; steps pressed meanwhile wait for it
+    pub fn step_duration(&self) -> f64 {
+        ANIMATION_SPEEDS[self.animation].1
+    }
+
+    // seconds per step when walking to a clicked cell
+    pub fn walk_speed(&self) -> f64 {
+        WALK_SPEEDS[self.walk].1
+    }
+}
+
+pub struct AppContext<'a> {
+    pub camera: &'a Camera2D,
+    pub fonts: &'a FontProvider,
+    pub entries: Vec<LevelEntry>,
+    // index of the level played last
+    pub current_level: usize,
+    pub level_chooser: LevelChooser<'a>,
+    pub game_board: GameBoard,
+    pub input_control: InputControl,
+    // command for the screen below, chosen in a menu on top of it
+    pub pending_command: Option<Command>,
+    pub save_data: SaveData,
+    pub settings: Settings,
+}
+
+impl<'a> AppContext<'a> {
+    pub fn new(
+        camera: &'a Camera2D,
+        fonts: &'a FontProvider,
+        entries: Vec<LevelEntry>,
+        game_board: GameBoard,
+    ) -> Self {
+        let labels = entries.iter().map(|entry| entry.label.clone()).collect();
+        let mut level_chooser = LevelChooser::new(camera, 920.0, 2., 350.0, labels, fonts);
+        let save_data = SaveData::load();
+        for (i, entry) in entries.iter().enumerate() {
+            if let Some(record) = save_data.record(&entry.source.key()) {
+                level_chooser.set_solved(i, score_text(record));
+            }
+        }
+        let current_level = save_data
+            .last_level()
+            .and_then(|key| entries.iter().position(|entry| entry.source.key() == key))
+            .unwrap_or(0);
+        level_chooser.set_selected(current_level);
+        Self {
+            camera,
+            fonts,
+            entries,
+            current_level,
+            level_chooser,
+            game_board,
+            input_control: InputControl::new(),
+            pending_command: None,
+            settings: Settings::load(&save_data),
+            save_data,
+        }
+    }
+
+    // Make the given level the current one, e.g. before playing it.
+    fn select_level(&mut self, index: usize) {
+        self.current_level = index;
+        self.level_chooser.set_selected(index);
+        self.save_data
+            .set_last_level(self.entries[index].source.key());
+        self.save_data.save();
+        self.game_board.reset_view();
+    }
+}
+
+pub enum Transition<'a> {
+    Stay,
+    Push(Box<dyn Screen<'a> + 'a>),
+    // remove this many screens from the top
+    Pop(usize),
+    // replace this many screens from the top
+    Replace(usize, Box<dyn Screen<'a> + 'a>),
+    // play the level with the given index; all screens but the title menu go
+    PlayLevel(usize),
+    ToTitle,
+    Quit,
+}
+
+pub trait Screen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a>;
+    fn draw(&self, ctx: &AppContext<'a>);
+    // Overlays are drawn on top of the screen below them.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+    // Called when the screen is removed from the stack.
+    fn leave(&mut self, _ctx: &mut AppContext<'a>) {}
+}
+
+pub struct ScreenStack<'a> {
+    // the title menu at the bottom
+    screens: Vec<Box<dyn Screen<'a> + 'a>>,
+}
+
+impl<'a> ScreenStack<'a> {
+    pub fn new(title: Box<dyn Screen<'a> + 'a>) -> Self {
+        Self {
+            screens: vec![title],
+        }
+    }
+
+    fn pop(&mut self, ctx: &mut AppContext<'a>, count: usize) {
+        for _ in 0..count {
+            if let Some(mut screen) = self.screens.pop() {
+                screen.leave(ctx);
+            }
+        }
+    }
+
+    // Update the top screen and make the transition it asks for.
+    // Returns false once the application should quit.
+    pub async fn update(&mut self, ctx: &mut AppContext<'a>) -> bool {
+        let Some(top) = self.screens.last_mut() else {
+            return false;
+        };
+        let transition = top.update(ctx);
+        if !matches!(transition, Transition::Stay) {
+            // presses and drags belong to the screen they started on
+            ctx.input_control = InputControl::new();
+        }
+        match transition {
+            Transition::Stay => {}
+            Transition::Push(screen) => self.screens.push(screen),
+            Transition::Pop(count) => self.pop(ctx, count),
+            Transition::Replace(count, screen) => {
+                self.pop(ctx, count);
+                self.screens.push(screen);
+            }
+            Transition::PlayLevel(index) => {
+                // the level left saves its progress before the next one is loaded
+                self.pop(ctx, self.screens.len() - 1);
+                ctx.select_level(index);
+                self.screens
+                    .push(Box::new(PlayScreen::load(ctx, index).await));
+            }
+            Transition::ToTitle => self.pop(ctx, self.screens.len() - 1),
+            Transition::Quit => self.pop(ctx, self.screens.len()),
+        }
+        !self.screens.is_empty()
+    }
+
+    // Draw the top screen, and the screens below it as far as it is an overlay.
+    pub fn draw(&self, ctx: &AppContext<'a>) {
+        let first = self
+            .screens
+            .iter()
+            .rposition(|screen| !screen.is_overlay())
+            .unwrap_or(0);
+        for screen in &self.screens[first..] {
+            screen.draw(ctx);
+        }
+    }
+}
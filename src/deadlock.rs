@@ -0,0 +1,180 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Simple deadlock detection: dead squares, frozen boxes and blocked 2x2 squares.
+
+use std::collections::VecDeque;
+
+use crate::game_logic::{GameCell, GameState, Point};
+use crate::input_control::{DIR_DOWN, DIR_LEFT, DIR_NO_MOVE, DIR_RIGHT, DIR_UP};
+
+const DIRECTIONS: [Point; 4] = [DIR_LEFT, DIR_UP, DIR_RIGHT, DIR_DOWN];
+
+// A board as the freeze check sees it; the detector and the solver keep
+// their cells in different ways.
+pub trait Board {
+    type Cell: Copy + PartialEq;
+    // the neighbours of a cell on the horizontal and on the vertical axis
+    fn axes(&self, cell: Self::Cell) -> [(Self::Cell, Self::Cell); 2];
+    fn is_wall(&self, cell: Self::Cell) -> bool;
+    fn is_dead_square(&self, cell: Self::Cell) -> bool;
+    fn is_occupied_by_box(&self, cell: Self::Cell) -> bool;
+}
+
+// The box on `cell` can't be moved along either axis anymore: on each axis a
+// neighbour is a wall, both neighbours are dead squares, or a neighbour is a
+// box that is frozen itself (checked with this box counting as a wall).
+pub fn is_frozen<B: Board>(board: &B, cell: B::Cell, walls: &mut Vec<B::Cell>) -> bool {
+    walls.push(cell);
+    let mut frozen = true;
+    for (a, b) in board.axes(cell) {
+        let is_wall = |c: B::Cell| board.is_wall(c) || walls.contains(&c);
+        let blocked = is_wall(a)
+            || is_wall(b)
+            || (board.is_dead_square(a) && board.is_dead_square(b))
+            || (board.is_occupied_by_box(a) && is_frozen(board, a, walls))
+            || (board.is_occupied_by_box(b) && is_frozen(board, b, walls));
+        if !blocked {
+            frozen = false;
+            break;
+        }
+    }
+    walls.pop();
+    frozen
+}
+
+// Minimal number of pushes needed to bring a box from each cell onto the given
+// sinks, ignoring all other boxes; None marks cells from where a box can never
+// reach one of the sinks.
+pub fn push_distances(state: &GameState, sinks: &[Point]) -> Vec<Option<u32>> {
+    let width = state.width();
+    let is_floor = |p: &Point| state.get_cell(p) != GameCell::Unmovable;
+    let mut distances = vec![None; (width * state.height()) as usize];
+    let mut queue = VecDeque::new();
+    for sink in sinks {
+        distances[(sink.y * width + sink.x) as usize] = Some(0);
+        queue.push_back(*sink);
+    }
+    // walk backwards: a box on `previous` can be pushed onto `current` if the
+    // player has room to stand behind it
+    while let Some(current) = queue.pop_front() {
+        let distance = distances[(current.y * width + current.x) as usize].unwrap();
+        for direction in DIRECTIONS {
+            let previous = current - direction;
+            let player = previous - direction;
+            if !is_floor(&previous) || !is_floor(&player) {
+                continue;
+            }
+            let index = (previous.y * width + previous.x) as usize;
+            if distances[index].is_none() {
+                distances[index] = Some(distance + 1);
+                queue.push_back(previous);
+            }
+        }
+    }
+    distances
+}
+
+pub fn sinks(state: &GameState) -> Vec<Point> {
+    let mut result = Vec::new();
+    for y in 0..state.height() {
+        for x in 0..state.width() {
+            let pos = Point { x, y };
+            let cell = state.get_cell(&pos);
+            if cell == GameCell::Sink || cell == GameCell::SinkWithBox {
+                result.push(pos);
+            }
+        }
+    }
+    result
+}
+
+pub struct DeadlockDetector {
+    width: i32,
+    // cells from where a box can never be pushed onto a sink, e.g. corners
+    dead_squares: Vec<bool>,
+}
+
+impl DeadlockDetector {
+    // Precompute the dead squares of a level.
+    pub fn new(state: &GameState) -> Self {
+        Self {
+            width: state.width(),
+            dead_squares: push_distances(state, &sinks(state))
+                .into_iter()
+                .map(|d| d.is_none())
+                .collect(),
+        }
+    }
+
+    pub fn is_dead_square(&self, pos: &Point) -> bool {
+        self.dead_squares
+            .get((pos.y * self.width + pos.x) as usize)
+            .copied()
+            .unwrap_or(true)
+    }
+
+    // Boxes that are not on a sink and can never reach one anymore.
+    pub fn deadlocked_boxes(&self, state: &GameState) -> Vec<Point> {
+        let mut result = Vec::new();
+        for y in 0..state.height() {
+            for x in 0..state.width() {
+                let pos = Point { x, y };
+                if state.get_cell(&pos) == GameCell::Box
+                    && (self.is_dead_square(&pos)
+                        || self.in_blocked_square(state, &pos)
+                        || is_frozen(&DetectorBoard(self, state), pos, &mut Vec::new()))
+                {
+                    result.push(pos);
+                }
+            }
+        }
+        result
+    }
+
+    // The box is part of a 2x2 square made of walls and boxes only.
+    fn in_blocked_square(&self, state: &GameState, pos: &Point) -> bool {
+        let blocked = |p: Point| {
+            let cell = state.get_cell(&p);
+            cell == GameCell::Unmovable || state.is_occupied_by_box(&p)
+        };
+        [
+            Point { x: -1, y: -1 },
+            Point { x: 0, y: -1 },
+            Point { x: -1, y: 0 },
+            Point { x: 0, y: 0 },
+        ]
+        .into_iter()
+        .any(|offset| {
+            let corner = *pos + offset;
+            [DIR_NO_MOVE, DIR_RIGHT, DIR_DOWN, DIR_RIGHT + DIR_DOWN]
+                .into_iter()
+                .all(|d| blocked(corner + d))
+        })
+    }
+}
+
+// The state of a game along with the dead squares of its level.
+struct DetectorBoard<'a>(&'a DeadlockDetector, &'a GameState);
+
+impl Board for DetectorBoard<'_> {
+    type Cell = Point;
+
+    fn axes(&self, pos: Point) -> [(Point, Point); 2] {
+        [
+            (pos + DIR_LEFT, pos + DIR_RIGHT),
+            (pos + DIR_UP, pos + DIR_DOWN),
+        ]
+    }
+
+    fn is_wall(&self, pos: Point) -> bool {
+        self.1.get_cell(&pos) == GameCell::Unmovable
+    }
+
+    fn is_dead_square(&self, pos: Point) -> bool {
+        self.0.is_dead_square(&pos)
+    }
+
+    fn is_occupied_by_box(&self, pos: Point) -> bool {
+        self.1.is_occupied_by_box(&pos)
+    }
+}
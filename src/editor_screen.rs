@@ -0,0 +1,56 @@
+// kanjiban
+// (C) 2025 by JoAn
+// The level editor as a screen; test plays are pushed on top of it, so the
+// edited level is kept while trying it out.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::game_logic::GameState;
+use crate::level_editor::{EditorAction, LevelEditor, save_level};
+use crate::level_validator::{Severity, validate_state};
+use crate::play_screen::{PlayScreen, apply_view_gesture};
+
+pub struct EditorScreen<'a> {
+    editor: LevelEditor<'a>,
+    notice: Option<String>,
+}
+
+impl<'a> EditorScreen<'a> {
+    pub fn new(ctx: &AppContext<'a>, state: GameState) -> Self {
+        Self {
+            editor: LevelEditor::new(ctx.camera, state, ctx.fonts),
+            notice: None,
+        }
+    }
+}
+
+impl<'a> Screen<'a> for EditorScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        let gesture = ctx.input_control.get_view_gesture(ctx.camera, true);
+        apply_view_gesture(&mut ctx.game_board, self.editor.state(), &gesture);
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop(1);
+        }
+        match self.editor.update(&ctx.game_board) {
+            Some(EditorAction::TestPlay) => {
+                let diagnostics = validate_state(self.editor.state());
+                if let Some(error) = diagnostics.iter().find(|d| d.severity == Severity::Error) {
+                    self.notice = Some(error.to_string());
+                } else {
+                    self.notice = None;
+                    let test_play = PlayScreen::test_play(ctx, self.editor.state().clone());
+                    return Transition::Push(Box::new(test_play));
+                }
+            }
+            Some(EditorAction::Save) => self.notice = Some(save_level(self.editor.state())),
+            Some(EditorAction::Done) => return Transition::Pop(1),
+            None => {}
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        self.editor.draw(&ctx.game_board, self.notice.as_deref());
+    }
+}
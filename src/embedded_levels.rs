@@ -0,0 +1,13 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Level files compiled into the binary by build.rs ("embedded-levels" feature).
+
+include!(concat!(env!("OUT_DIR"), "/embedded_levels.rs"));
+
+// Contents of a file of the levels directory, e.g. "levels/level_0.lvl".
+pub fn embedded_file(path: &str) -> Option<&'static str> {
+    EMBEDDED_FILES
+        .iter()
+        .find(|(name, _)| *name == path)
+        .map(|(_, contents)| *contents)
+}
@@ -2,7 +2,9 @@
 // (C) 2025 by JoAn
 // Drawing game states to the screen using a game board.
 
+use crate::animation::BoardAnimation;
 use crate::game_logic::*;
+use crate::macroquad_helpers::FontProvider;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
@@ -13,6 +15,8 @@ const UNMOVABLE_SPRITE: &str = "silver_plate.png";
 const SINK_SPRITE: &str = "target_plate.png";
 const SINK_WITH_BOX_SPRITE: &str = "saved_box.png";
 const EMPTY_SPRITE: &str = "empty.png";
+// zoom relative to the size that fits the whole level
+const MAX_ZOOM: f32 = 4.;
 
 pub struct SpriteManager {
     sprites: HashMap<GameCell, Texture2D>,
@@ -54,12 +58,16 @@ impl SpriteManager {
     }
 }
 
+// The board area is given by its top left corner and its size (max_x, max_y).
 pub struct GameBoard {
     sprites: SpriteManager,
     top_x: f32,
     top_y: f32,
     max_x: f32,
     max_y: f32,
+    zoom: f32,
+    // offset of the zoomed board from its centered position
+    pan: Vec2,
 }
 
 impl GameBoard {
@@ -70,69 +78,265 @@ impl GameBoard {
             top_y,
             max_x,
             max_y,
+            zoom: 1.,
+            pan: Vec2::ZERO,
         }
     }
 }
 
 impl GameBoard {
-    pub fn draw_board(self: &GameBoard, game_state: &GameState) {
+    // Cells are as large as possible while the whole level fits (at zoom 1).
+    fn square_size(&self, game_state: &GameState) -> f32 {
+        let fit =
+            (self.max_x / game_state.width() as f32).min(self.max_y / game_state.height() as f32);
+        fit * self.zoom
+    }
+
+    fn board_size(&self, game_state: &GameState) -> Vec2 {
+        let sq_size = self.square_size(game_state);
+        vec2(
+            game_state.width() as f32 * sq_size,
+            game_state.height() as f32 * sq_size,
+        )
+    }
+
+    // Top left corner of the board; it is centered in the board area.
+    fn origin(&self, game_state: &GameState) -> Vec2 {
+        let area = vec2(self.max_x, self.max_y);
+        vec2(self.top_x, self.top_y) + (area - self.board_size(game_state)) / 2. + self.pan
+    }
+
+    // Keep zoomed boards covering the area, and smaller ones centered.
+    fn clamp_pan(&mut self, game_state: &GameState) {
+        if game_state.width() == 0 || game_state.height() == 0 {
+            self.pan = Vec2::ZERO;
+            return;
+        }
+        let overhang =
+            ((self.board_size(game_state) - vec2(self.max_x, self.max_y)) / 2.).max(Vec2::ZERO);
+        self.pan = self.pan.clamp(-overhang, overhang);
+    }
+
+    pub fn reset_view(&mut self) {
+        self.zoom = 1.;
+        self.pan = Vec2::ZERO;
+    }
+
+    // Zoom by the given factor, keeping the board position at `center` in place.
+    pub fn zoom_at(&mut self, game_state: &GameState, factor: f32, center: Vec2) {
+        if game_state.width() == 0 || game_state.height() == 0 {
+            return;
+        }
+        let old_origin = self.origin(game_state);
+        let old_size = self.square_size(game_state);
+        self.zoom = (self.zoom * factor).clamp(1., MAX_ZOOM);
+        let scale = self.square_size(game_state) / old_size;
+        let new_origin = center - (center - old_origin) * scale;
+        self.pan += new_origin - self.origin(game_state);
+        self.clamp_pan(game_state);
+    }
+
+    pub fn pan_by(&mut self, game_state: &GameState, delta: Vec2) {
+        self.pan += delta;
+        self.clamp_pan(game_state);
+    }
+
+    pub fn center(&self) -> Vec2 {
+        vec2(self.top_x + self.max_x / 2., self.top_y + self.max_y / 2.)
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        Rect::new(self.top_x, self.top_y, self.max_x, self.max_y).contains(position)
+    }
+
+    // The cell of the game state shown at the given position, if any.
+    pub fn cell_at(&self, game_state: &GameState, position: Vec2) -> Option<Point> {
+        if !self.contains(position) {
+            return None;
+        }
+        let sq_size = self.square_size(game_state);
+        let origin = self.origin(game_state);
+        let x = ((position.x - origin.x) / sq_size).floor();
+        let y = ((position.y - origin.y) / sq_size).floor();
+        if x < 0. || y < 0. || x >= game_state.width() as f32 || y >= game_state.height() as f32 {
+            return None;
+        }
+        Some(Point {
+            x: x as i32,
+            y: y as i32,
+        })
+    }
+
+    // Draw the game state, with player and boxes where the animation has them;
+    // deadlocked boxes are tinted and a notice tells the player that the
+    // position is lost.
+    pub fn draw_board(
+        self: &GameBoard,
+        game_state: &GameState,
+        deadlocked_boxes: &[Point],
+        animation: &BoardAnimation,
+    ) {
         clear_background(LIGHTGRAY);
         if game_state.width() == 0 || game_state.height() == 0 {
             return;
         }
-        let sq_size =
-            (self.max_x.min(self.max_y)) / (game_state.height().min(game_state.width()) as f32);
+        let sq_size = self.square_size(game_state);
+        let origin = self.origin(game_state);
 
         draw_rectangle(self.top_x, self.top_y, self.max_x, self.max_y, BEIGE);
 
         let draw_point = |p: &Point, kind: GameCell| {
             self.sprites.draw_sprite(
                 kind,
-                self.top_x + p.x as f32 * sq_size,
-                self.top_y + p.y as f32 * sq_size,
+                origin.x + p.x as f32 * sq_size,
+                origin.y + p.y as f32 * sq_size,
                 sq_size,
                 sq_size,
             )
         };
+        // draw at a position in cells, scaled around the cell's center
+        let draw_at = |p: Vec2, kind: GameCell, scale: f32| {
+            let size = sq_size * scale;
+            let offset = (sq_size - size) / 2.;
+            self.sprites.draw_sprite(
+                kind,
+                origin.x + p.x * sq_size + offset,
+                origin.y + p.y * sq_size + offset,
+                size,
+                size,
+            )
+        };
+        let now = get_time();
+        // fixed cells first, so moving sprites are drawn on top of them
         for x in 0..game_state.width() {
             for y in 0..game_state.height() {
-                let pos = Point {
-                    x: x as i32,
-                    y: y as i32,
-                };
+                let pos = Point { x, y };
                 draw_point(&pos, GameCell::Empty);
                 match game_state.get_cell(&pos) {
-                    GameCell::Box => draw_point(&pos, GameCell::Box),
                     GameCell::Sink => draw_point(&pos, GameCell::Sink),
-                    GameCell::SinkWithBox => draw_point(&pos, GameCell::SinkWithBox),
+                    GameCell::SinkWithBox if animation.is_box_moving(&pos, now) => {
+                        draw_point(&pos, GameCell::Sink)
+                    }
                     GameCell::Unmovable => draw_point(&pos, GameCell::Unmovable),
                     _ => {}
                 }
             }
         }
-        draw_point(&game_state.get_player_position(), GameCell::Player);
+        for x in 0..game_state.width() {
+            for y in 0..game_state.height() {
+                let pos = Point { x, y };
+                let kind = match game_state.get_cell(&pos) {
+                    GameCell::Box => GameCell::Box,
+                    // show the box as saved once it arrived
+                    GameCell::SinkWithBox if animation.is_box_moving(&pos, now) => GameCell::Box,
+                    GameCell::SinkWithBox => GameCell::SinkWithBox,
+                    _ => continue,
+                };
+                draw_at(
+                    animation.box_position(&pos, now),
+                    kind,
+                    animation.box_scale(&pos, now),
+                );
+            }
+        }
+        draw_at(
+            animation.player_position(game_state, now),
+            GameCell::Player,
+            1.,
+        );
+        for pos in deadlocked_boxes {
+            let p = animation.box_position(pos, now);
+            draw_rectangle(
+                origin.x + p.x * sq_size,
+                origin.y + p.y * sq_size,
+                sq_size,
+                sq_size,
+                Color::new(0.9, 0.1, 0.1, 0.45),
+            );
+        }
     }
 
-    pub fn draw_win(self: &GameBoard, game_state: &GameState) {
-        self.draw_board(game_state);
-        clear_background(WHITE);
-        let text = "You won!";
-        let font_size = 30.;
-        let text_size = measure_text(text, None, font_size as _, 1.0);
-        let sq_size = screen_height() / game_state.width().max(game_state.height()) as f32;
-        self.sprites.draw_sprite(
-            GameCell::Player,
-            screen_width() / 2. - text_size.width / 2. - 70.0,
-            screen_height() / 2. + text_size.height / 2.,
+    // Tell the player that the position can't be solved anymore.
+    pub fn draw_lost_notice(&self, fonts: &FontProvider) {
+        let text = "Position is lost - undo?";
+        let font_size = 40;
+        let text_size = measure_text(text, Some(fonts.font()), font_size, 1.0);
+        let x = self.top_x + (self.max_x - text_size.width) / 2.;
+        let y = self.top_y + self.max_y - 20.;
+        draw_rectangle(
+            x - 10.,
+            y - text_size.offset_y - 10.,
+            text_size.width + 20.,
+            text_size.height + 20.,
+            WHITE,
+        );
+        draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font: Some(fonts.font()),
+                font_size,
+                color: MAROON,
+                ..Default::default()
+            },
+        );
+    }
+
+    // Mark the box selected for moving.
+    pub fn draw_selection(self: &GameBoard, game_state: &GameState, box_position: &Point) {
+        let sq_size = self.square_size(game_state);
+        let origin = self.origin(game_state);
+        draw_rectangle_lines(
+            origin.x + box_position.x as f32 * sq_size,
+            origin.y + box_position.y as f32 * sq_size,
             sq_size,
             sq_size,
+            sq_size / 8.,
+            SKYBLUE,
         );
-        draw_text(
-            text,
-            screen_width() / 2. - text_size.width / 2.,
-            screen_height() / 2. + text_size.height / 2.,
-            font_size,
-            DARKGRAY,
+    }
+
+    // Highlight a box and show the direction it should be pushed to.
+    pub fn draw_hint(
+        self: &GameBoard,
+        game_state: &GameState,
+        box_position: &Point,
+        direction: &Point,
+    ) {
+        let sq_size = self.square_size(game_state);
+        let origin = self.origin(game_state);
+        let x = origin.x + box_position.x as f32 * sq_size;
+        let y = origin.y + box_position.y as f32 * sq_size;
+        draw_rectangle_lines(x, y, sq_size, sq_size, sq_size / 8., GOLD);
+        // arrow in the middle of the cell the box will be pushed onto
+        let center = vec2(x + sq_size / 2., y + sq_size / 2.);
+        let forward = vec2(direction.x as f32, direction.y as f32);
+        let side = vec2(-forward.y, forward.x);
+        let tip = center + forward * sq_size * 1.4;
+        let base = center + forward * sq_size * 0.7;
+        draw_triangle(
+            tip,
+            base + side * sq_size * 0.3,
+            base - side * sq_size * 0.3,
+            GOLD,
         );
     }
+
+    // Draw the sprite of a cell kind anywhere, e.g. in a palette.
+    pub fn draw_tile(self: &GameBoard, kind: GameCell, rect: Rect) {
+        self.sprites
+            .draw_sprite(kind, rect.x, rect.y, rect.w, rect.h);
+    }
+
+    // Cover whatever a zoomed board draws outside of the board area.
+    pub fn draw_margins(self: &GameBoard) {
+        let far = 10_000.;
+        let right = self.top_x + self.max_x;
+        let bottom = self.top_y + self.max_y;
+        draw_rectangle(-far, -far, 2. * far, far + self.top_y, LIGHTGRAY);
+        draw_rectangle(-far, bottom, 2. * far, far, LIGHTGRAY);
+        draw_rectangle(-far, self.top_y, far + self.top_x, self.max_y, LIGHTGRAY);
+        draw_rectangle(right, self.top_y, far, self.max_y, LIGHTGRAY);
+    }
 }
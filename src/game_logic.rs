@@ -13,9 +13,13 @@ pub enum GameCell {
     Unmovable,
 }
 
-use std::ops::Add;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Add, Sub};
 
-use crate::input_control::DIR_NO_MOVE;
+use crate::deadlock::DeadlockDetector;
+use crate::input_control::{DIR_DOWN, DIR_LEFT, DIR_NO_MOVE, DIR_RIGHT, DIR_UP};
+use crate::lurd::{LurdError, LurdErrorKind, char_to_direction, move_to_char, to_lurd};
+use crate::rle::decode_rle;
 
 #[derive(Hash, Clone, Copy, PartialEq, Eq)]
 pub struct Point {
@@ -34,6 +38,24 @@ impl Add for Point {
     }
 }
 
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+// A single successful player step; `pushed` tells whether a box was moved along.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub direction: Point,
+    pub pushed: bool,
+}
+
 #[derive(Hash, Clone, PartialEq, Eq)]
 pub struct GameState {
     width: i32,
@@ -47,7 +69,7 @@ impl GameState {
         Self {
             width: width as i32,
             height: height as i32,
-            cells: vec![GameCell::Empty; (width * height) as usize],
+            cells: vec![GameCell::Empty; width as usize * height as usize],
             joan: Point { x: 0, y: 0 },
         }
     }
@@ -69,10 +91,7 @@ impl GameState {
     }
 
     pub fn get_cell(&self, position: &Point) -> GameCell {
-        if position.x >= 0
-            && position.x < self.width as i32
-            && position.y >= 0
-            && position.y < self.height as i32
+        if position.x >= 0 && position.x < self.width && position.y >= 0 && position.y < self.height
         {
             self.cells[(position.y * self.width + position.x) as usize]
         } else {
@@ -81,21 +100,35 @@ impl GameState {
     }
 
     pub fn set_cell(&mut self, position: &Point, cell: GameCell) {
-        if position.x >= 0
-            && position.x < self.width as i32
-            && position.y >= 0
-            && position.y < self.height as i32
+        if position.x >= 0 && position.x < self.width && position.y >= 0 && position.y < self.height
         {
             self.cells[(position.y * self.width + position.x) as usize] = cell;
         }
     }
 
-    fn is_empty_or_sink(&self, pos: &Point) -> bool {
-        let cell = self.get_cell(&pos);
+    // A copy with the given size; cells outside the old grid are empty floor,
+    // the player is moved into the grid if it would fall outside.
+    pub fn resized(&self, width: u16, height: u16) -> GameState {
+        let mut resized = GameState::new(width, height);
+        for y in 0..resized.height.min(self.height) {
+            for x in 0..resized.width.min(self.width) {
+                let pos = Point { x, y };
+                resized.set_cell(&pos, self.get_cell(&pos));
+            }
+        }
+        resized.joan = Point {
+            x: self.joan.x.min(resized.width - 1).max(0),
+            y: self.joan.y.min(resized.height - 1).max(0),
+        };
+        resized
+    }
+
+    pub fn is_empty_or_sink(&self, pos: &Point) -> bool {
+        let cell = self.get_cell(pos);
         cell == GameCell::Empty || cell == GameCell::Sink
     }
 
-    fn is_occupied_by_box(&self, pos: &Point) -> bool {
+    pub fn is_occupied_by_box(&self, pos: &Point) -> bool {
         let cell = self.get_cell(pos);
         cell == GameCell::Box || cell == GameCell::SinkWithBox
     }
@@ -103,7 +136,7 @@ impl GameState {
     // Return true iff box was successfully moved from old_pos to new_pos;
     // this includes a check whether old_pos really contained a box.
     fn try_move_box(&mut self, old_pos: &Point, new_pos: &Point) -> bool {
-        if !self.is_empty_or_sink(&new_pos) | !self.is_occupied_by_box(&old_pos) {
+        if !self.is_empty_or_sink(new_pos) | !self.is_occupied_by_box(old_pos) {
             return false;
         }
         let old_cell = self.get_cell(old_pos);
@@ -122,99 +155,764 @@ impl GameState {
         {
             self.set_cell(old_pos, GameCell::Empty);
         }
-        return true;
+        true
     }
 
-    // Return true iff the player can move in the desired direction,
-    // and modify the game state. Otherwise return false.
-    pub fn try_perform_move(&mut self, direction: &Point) -> bool {
+    // Return the performed move iff the player can move in the desired direction,
+    // and modify the game state. Otherwise return None.
+    pub fn try_perform_move(&mut self, direction: &Point) -> Option<Move> {
         if *direction == DIR_NO_MOVE {
-            return false;
+            return None;
         }
         let desired_position = *direction + self.get_player_position();
-        if self.is_empty_or_sink(&desired_position) {
-            self.set_player_position(&desired_position);
-            return true;
-        } else {
+        let mut pushed = false;
+        if !self.is_empty_or_sink(&desired_position) {
             if !self.try_move_box(&desired_position, &(desired_position + *direction)) {
-                return false;
+                return None;
             }
-            self.set_player_position(&desired_position);
-            return true;
+            pushed = true;
         }
+        self.set_player_position(&desired_position);
+        Some(Move {
+            direction: *direction,
+            pushed,
+        })
     }
 
-    // checks if the level is solved
-    pub fn all_boxes_on_sinks(&self) -> bool {
-        !self.cells.iter().any(|c| *c == GameCell::Box)
+    // The level to be played backwards: boxes on the sinks, and sinks where
+    // the boxes are.
+    pub fn reversed(&self) -> GameState {
+        let mut reversed = self.clone();
+        for cell in reversed.cells.iter_mut() {
+            *cell = match *cell {
+                GameCell::Box => GameCell::Sink,
+                GameCell::Sink => GameCell::Box,
+                other => other,
+            };
+        }
+        reversed
     }
 
-    pub fn print(&self) {
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                let pos = Point { x, y };
-                let output = match self.get_cell(&pos) {
-                    GameCell::Box => 'b',
-                    GameCell::Empty => '_',
-                    GameCell::Player => '@',
-                    GameCell::Sink => '.',
-                    GameCell::SinkWithBox => 'B',
-                    GameCell::Unmovable => '#',
-                    _ => '?',
-                };
-                if pos == self.get_player_position() {
-                    print!("P");
-                } else {
-                    print!("{output}");
+    // Like try_perform_move, but for playing backwards: boxes in the way are
+    // never pushed, a box right behind the player is pulled along iff pull.
+    pub fn try_perform_pull(&mut self, direction: &Point, pull: bool) -> Option<Move> {
+        if *direction == DIR_NO_MOVE {
+            return None;
+        }
+        let position = self.get_player_position();
+        let desired_position = position + *direction;
+        if !self.is_empty_or_sink(&desired_position) {
+            return None;
+        }
+        let pulled = pull && self.try_move_box(&(position - *direction), &position);
+        self.set_player_position(&desired_position);
+        Some(Move {
+            direction: *direction,
+            pushed: pulled,
+        })
+    }
+
+    // Take back a move previously returned by try_perform_pull; a pulled box
+    // is pushed back to where it was.
+    pub fn undo_pull(&mut self, performed: &Move) {
+        let position = self.get_player_position() - performed.direction;
+        if performed.pushed {
+            self.try_move_box(&position, &(position - performed.direction));
+        }
+        self.set_player_position(&position);
+    }
+
+    // Take back a move previously returned by try_perform_move; a pushed box
+    // is pulled back onto the cell the player is leaving.
+    pub fn undo_move(&mut self, performed: &Move) {
+        let position = self.get_player_position();
+        if performed.pushed {
+            self.try_move_box(&(position + performed.direction), &position);
+        }
+        self.set_player_position(&(position - performed.direction));
+    }
+
+    // Shortest walk (as a list of directions) from the player to target that
+    // does not push any box; None if target can't be reached this way.
+    pub fn find_path(&self, target: &Point) -> Option<Vec<Point>> {
+        let start = self.get_player_position();
+        if start == *target {
+            return Some(Vec::new());
+        }
+        if !self.is_empty_or_sink(target) {
+            return None;
+        }
+        let index = |p: &Point| (p.y * self.width + p.x) as usize;
+        // direction used to enter each visited cell
+        let mut came_from: Vec<Option<Point>> = vec![None; self.cells.len()];
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for direction in [DIR_LEFT, DIR_UP, DIR_RIGHT, DIR_DOWN] {
+                let next = current + direction;
+                if next == start
+                    || !self.is_empty_or_sink(&next)
+                    || came_from[index(&next)].is_some()
+                {
+                    continue;
+                }
+                came_from[index(&next)] = Some(direction);
+                if next == *target {
+                    let mut path = Vec::new();
+                    let mut p = next;
+                    while p != start {
+                        let d = came_from[index(&p)].unwrap();
+                        path.push(d);
+                        p = p - d;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    // Moves (walks and pushes, fewest pushes first) that bring the box on
+    // `from` to `to` without touching any other box; None if there are none.
+    pub fn find_push_path(&self, from: &Point, to: &Point) -> Option<Vec<Point>> {
+        if !self.is_occupied_by_box(from) {
+            return None;
+        }
+        let directions = [DIR_LEFT, DIR_UP, DIR_RIGHT, DIR_DOWN];
+        let mut without_box = self.clone();
+        without_box.set_cell(
+            from,
+            if self.get_cell(from) == GameCell::SinkWithBox {
+                GameCell::Sink
+            } else {
+                GameCell::Empty
+            },
+        );
+        let with_box = |box_pos: &Point, player: &Point| {
+            let mut state = without_box.clone();
+            let cell = if state.get_cell(box_pos) == GameCell::Sink {
+                GameCell::SinkWithBox
+            } else {
+                GameCell::Box
+            };
+            state.set_cell(box_pos, cell);
+            state.set_player_position(player);
+            state
+        };
+        // 0-1 BFS over box position and the side the player stands on; walking
+        // around the box is free, each push costs one. Nodes are settled when
+        // taken from the queue, as a cheaper way may still turn up before.
+        let mut pushes_to: HashMap<(Point, Point), u32> = HashMap::new();
+        let mut came_from: HashMap<(Point, Point), Option<(Point, Point)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for direction in directions {
+            if self.find_path(&(*from - direction)).is_some() {
+                pushes_to.insert((*from, direction), 0);
+                came_from.insert((*from, direction), None);
+                queue.push_back((*from, direction, 0));
+            }
+        }
+        let mut goal = None;
+        while let Some((box_pos, side, pushes)) = queue.pop_front() {
+            if pushes_to[&(box_pos, side)] < pushes {
+                continue;
+            }
+            if box_pos == *to {
+                goal = Some((box_pos, side));
+                break;
+            }
+            let mut relax = |node: (Point, Point), cost: u32| {
+                let improves = pushes_to
+                    .get(&node)
+                    .is_none_or(|known| pushes + cost < *known);
+                if improves {
+                    pushes_to.insert(node, pushes + cost);
+                    came_from.insert(node, Some((box_pos, side)));
+                }
+                improves
+            };
+            let state = with_box(&box_pos, &(box_pos - side));
+            for direction in directions {
+                if direction != side
+                    && state.find_path(&(box_pos - direction)).is_some()
+                    && relax((box_pos, direction), 0)
+                {
+                    queue.push_front((box_pos, direction, pushes));
                 }
             }
-            println!();
+            let pushed = box_pos + side;
+            if without_box.is_empty_or_sink(&pushed) && relax((pushed, side), 1) {
+                queue.push_back((pushed, side, pushes + 1));
+            }
         }
+        // collect the pushes and replay them, walking in between
+        let mut pushes = Vec::new();
+        let mut node = goal?;
+        while let Some(previous) = came_from[&node] {
+            if previous.0 != node.0 {
+                pushes.push(previous);
+            }
+            node = previous;
+        }
+        pushes.reverse();
+        let mut state = self.clone();
+        let mut path = Vec::new();
+        for (box_pos, direction) in pushes {
+            for step in state.find_path(&(box_pos - direction))? {
+                state.try_perform_move(&step);
+                path.push(step);
+            }
+            state.try_perform_move(&direction)?;
+            path.push(direction);
+        }
+        Some(path)
+    }
+
+    // checks if the level is solved; a level without boxes never is
+    pub fn all_boxes_on_sinks(&self) -> bool {
+        self.cells.contains(&GameCell::SinkWithBox) && !self.cells.contains(&GameCell::Box)
     }
 }
 
+// A step taken back by undo, which redo takes again.
+#[derive(Clone, Copy)]
+enum Undone {
+    Move(Move),
+    Reset,
+}
+
 pub struct Game {
-    steps: i16,
+    moves: u64,
+    pushes: u64,
     title: String,
     author: String,
+    initial: GameState,
     state: GameState,
+    history: Vec<Move>,
+    undone: Vec<Undone>,
+    // histories taken back by resets, the latest last; undo restores them
+    reset_histories: Vec<Vec<Move>>,
+    // when playing backwards, the level as played forwards
+    forward: Option<GameState>,
+    deadlocks: DeadlockDetector,
+    deadlocked: Vec<Point>,
+    // directions still to be walked, one per tick
+    planned: VecDeque<Point>,
 }
 
 impl Game {
     pub fn new(initial: GameState, title: String, author: String) -> Self {
+        let deadlocks = DeadlockDetector::new(&initial);
+        let deadlocked = deadlocks.deadlocked_boxes(&initial);
         Self {
-            steps: 0,
+            moves: 0,
+            pushes: 0,
             title,
             author,
-            state: initial,
+            state: initial.clone(),
+            initial,
+            history: Vec::new(),
+            undone: Vec::new(),
+            reset_histories: Vec::new(),
+            forward: None,
+            deadlocks,
+            deadlocked,
+            planned: VecDeque::new(),
         }
     }
 
+    // A game playing the level backwards, from the start: the boxes are pulled
+    // from the sinks to where they start. Deadlocks aren't detected then.
+    pub fn reversed(&self) -> Game {
+        let mut reversed = Game::new(
+            self.initial.reversed(),
+            self.title.clone(),
+            self.author.clone(),
+        );
+        reversed.forward = Some(self.initial.clone());
+        reversed.deadlocked.clear();
+        reversed
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        self.forward.is_some()
+    }
+
     pub fn state(&self) -> &GameState {
         &self.state
     }
 
+    // The level as it was before the first move.
+    pub fn initial_state(&self) -> &GameState {
+        &self.initial
+    }
+
     pub fn title(&self) -> &str {
         &self.title
     }
 
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
     pub fn author(&self) -> &str {
         &self.author
     }
 
-    pub fn steps(&self) -> i16 {
-        self.steps
+    pub fn set_author(&mut self, author: String) {
+        self.author = author;
+    }
+
+    // Number of player steps, including the ones pushing a box.
+    pub fn moves(&self) -> u64 {
+        self.moves
+    }
+
+    // Number of steps that moved a box; pulls when playing backwards.
+    pub fn pushes(&self) -> u64 {
+        self.pushes
+    }
+
+    fn count(&mut self, performed: &Move) {
+        self.moves += 1;
+        if performed.pushed {
+            self.pushes += 1;
+        }
+    }
+
+    fn uncount(&mut self, performed: &Move) {
+        self.moves -= 1;
+        if performed.pushed {
+            self.pushes -= 1;
+        }
     }
 
+    // Boxes that can't reach a sink anymore; the position is lost if there are any.
+    pub fn deadlocked_boxes(&self) -> &[Point] {
+        &self.deadlocked
+    }
+
+    fn update_deadlocks(&mut self, performed: &Move) {
+        if performed.pushed && !self.is_reverse() {
+            self.deadlocked = self.deadlocks.deadlocked_boxes(&self.state);
+        }
+    }
+
+    // Perform a step on the state; playing backwards, a box behind the player
+    // is pulled along iff pull.
+    fn step_state(&mut self, direction: &Point, pull: bool) -> Option<Move> {
+        if self.is_reverse() {
+            self.state.try_perform_pull(direction, pull)
+        } else {
+            self.state.try_perform_move(direction)
+        }
+    }
+
+    // A step of the player; playing backwards, it pulls a box behind the player.
     pub fn try_perform_move(&mut self, direction: &Point) -> bool {
-        if self.state.try_perform_move(direction) {
-            self.steps += 1;
+        self.try_perform_step(direction, true)
+    }
+
+    fn try_perform_step(&mut self, direction: &Point, pull: bool) -> bool {
+        if let Some(performed) = self.step_state(direction, pull) {
+            self.count(&performed);
+            self.update_deadlocks(&performed);
+            self.history.push(performed);
+            self.undone.clear();
+            return true;
+        }
+        false
+    }
+
+    // Queue moves that are performed one by one via perform_planned_move;
+    // replaces any earlier plan.
+    pub fn plan_moves(&mut self, directions: Vec<Point>) {
+        self.planned = VecDeque::from(directions);
+    }
+
+    pub fn has_plan(&self) -> bool {
+        !self.planned.is_empty()
+    }
+
+    pub fn cancel_plan(&mut self) {
+        self.planned.clear();
+    }
+
+    // Perform the next planned move; the rest of the plan is dropped if it
+    // turns out to be impossible. Playing backwards, planned walks don't pull.
+    pub fn perform_planned_move(&mut self) -> bool {
+        if let Some(direction) = self.planned.pop_front() {
+            if self.try_perform_step(&direction, false) {
+                return true;
+            }
+            self.planned.clear();
+        }
+        false
+    }
+
+    // Plan the shortest walk to target that doesn't push any box; returns
+    // false if there is none.
+    pub fn plan_walk(&mut self, target: &Point) -> bool {
+        match self.state.find_path(target) {
+            Some(path) => {
+                self.plan_moves(path);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Plan the moves bringing the box on `from` to `to`; returns false if
+    // that's not possible without moving other boxes, or if playing backwards.
+    pub fn plan_push(&mut self, from: &Point, to: &Point) -> bool {
+        if self.is_reverse() {
+            return false;
+        }
+        match self.state.find_push_path(from, to) {
+            Some(path) => {
+                self.plan_moves(path);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Playing backwards, the player may start anywhere: before the first pull,
+    // it can be put on any free cell. Returns false if that's not possible.
+    pub fn jump_to(&mut self, target: &Point) -> bool {
+        if !self.is_reverse() || self.pushes > 0 || !self.initial.is_empty_or_sink(target) {
+            return false;
+        }
+        // the walks so far don't matter, the game starts over from the target
+        self.cancel_plan();
+        self.initial.set_player_position(target);
+        self.reset_histories.clear();
+        self.undone.clear();
+        self.restore(Vec::new());
+        true
+    }
+
+    // Go back to the start of the level. The moves made are kept, so undo
+    // brings them back; returns false if there were none.
+    pub fn reset(&mut self) -> bool {
+        self.cancel_plan();
+        if self.history.is_empty() {
+            return false;
+        }
+        self.undone.clear();
+        self.restart();
+        true
+    }
+
+    // Keep the moves made for undo and start over.
+    fn restart(&mut self) {
+        self.reset_histories.push(std::mem::take(&mut self.history));
+        self.restore(Vec::new());
+    }
+
+    // Replay the given history from the start of the level.
+    fn restore(&mut self, history: Vec<Move>) {
+        self.state = self.initial.clone();
+        self.moves = 0;
+        self.pushes = 0;
+        for performed in &history {
+            self.step_state(&performed.direction, performed.pushed);
+            self.count(performed);
+        }
+        self.history = history;
+        if !self.is_reverse() {
+            self.deadlocked = self.deadlocks.deadlocked_boxes(&self.state);
+        }
+    }
+
+    // Take back the last move, or the last reset if there are no moves since;
+    // returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.cancel_plan();
+        if self.history.is_empty()
+            && let Some(history) = self.reset_histories.pop()
+        {
+            // moves undone after the reset can't be redone on the old history
+            self.undone.clear();
+            self.undone.push(Undone::Reset);
+            self.restore(history);
+            return true;
+        }
+        if let Some(performed) = self.history.pop() {
+            if self.is_reverse() {
+                self.state.undo_pull(&performed);
+            } else {
+                self.state.undo_move(&performed);
+            }
+            self.uncount(&performed);
+            self.update_deadlocks(&performed);
+            self.undone.push(Undone::Move(performed));
             return true;
         }
         false
     }
 
+    // Perform the last undone move or reset again; returns false if there is
+    // nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.cancel_plan();
+        match self.undone.pop() {
+            Some(Undone::Reset) => {
+                self.restart();
+                true
+            }
+            Some(Undone::Move(performed)) => {
+                if self
+                    .step_state(&performed.direction, performed.pushed)
+                    .is_some()
+                {
+                    self.count(&performed);
+                    self.update_deadlocks(&performed);
+                    self.history.push(performed);
+                    return true;
+                }
+                self.undone.clear();
+                false
+            }
+            None => false,
+        }
+    }
+
+    // The moves performed so far, in LURD notation.
+    pub fn lurd(&self) -> String {
+        to_lurd(&self.history)
+    }
+
+    // Perform all moves of a LURD string, which may be run-length encoded;
+    // whitespace is skipped. Uppercase letters must push, lowercase ones must
+    // not; playing backwards, they pull instead.
+    // On error, the moves before the offending character remain performed;
+    // the error position refers to the decoded string.
+    pub fn replay_lurd(&mut self, lurd: &str) -> Result<(), LurdError> {
+        let decoded = decode_rle(lurd).map_err(|error| LurdError {
+            index: 0,
+            kind: LurdErrorKind::Rle(error),
+        })?;
+        for (index, c) in decoded.chars().enumerate() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let Some(direction) = char_to_direction(c) else {
+                return Err(LurdError {
+                    index,
+                    kind: LurdErrorKind::UnknownCharacter(c),
+                });
+            };
+            if !self.try_perform_step(&direction, c.is_ascii_uppercase()) {
+                return Err(LurdError {
+                    index,
+                    kind: LurdErrorKind::IllegalMove,
+                });
+            }
+            let performed = self.history[self.history.len() - 1];
+            if performed.pushed != c.is_ascii_uppercase() {
+                self.undo();
+                self.undone.clear();
+                return Err(LurdError {
+                    index,
+                    kind: LurdErrorKind::WrongCase(move_to_char(&performed)),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Playing backwards, the game is won once the boxes are back where they
+    // start and the player can walk to its start.
     pub fn is_game_won(&self) -> bool {
         self.state.all_boxes_on_sinks()
+            && self.forward.as_ref().is_none_or(|forward| {
+                self.state
+                    .find_path(&forward.get_player_position())
+                    .is_some()
+            })
+    }
+
+    // The solution a won backwards game stands for, in LURD notation: the walk
+    // to where the last pull ended, then all pulls taken back as pushes.
+    pub fn forward_lurd(&self) -> Option<String> {
+        let forward = self.forward.as_ref()?;
+        if !self.is_game_won() {
+            return None;
+        }
+        let walk = forward.find_path(&self.state.get_player_position())?;
+        let mut moves: Vec<Move> = walk
+            .into_iter()
+            .map(|direction| Move {
+                direction,
+                pushed: false,
+            })
+            .collect();
+        moves.extend(self.history.iter().rev().map(|performed| Move {
+            direction: DIR_NO_MOVE - performed.direction,
+            pushed: performed.pushed,
+        }));
+        Some(to_lurd(&moves))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_loader::LevelLoader;
+
+    fn game(level: &str) -> Game {
+        LevelLoader::parse_str(level).unwrap()
+    }
+
+    #[test]
+    fn empty_level_is_not_won() {
+        let mut game = Game::new(GameState::new(0, 0), String::new(), String::new());
+        assert!(!game.undo());
+        assert!(!game.is_game_won());
+    }
+
+    #[test]
+    fn level_without_boxes_is_not_won() {
+        let mut state = GameState::new(3, 1);
+        state.set_cell(&Point { x: 2, y: 0 }, GameCell::Sink);
+        let game = Game::new(state, String::new(), String::new());
+        assert!(!game.is_game_won());
+    }
+
+    #[test]
+    fn last_push_wins() {
+        let mut game = game("#####\n#@$.#\n#####\n");
+        assert!(!game.is_game_won());
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(game.is_game_won());
+    }
+    // the box at x 3 goes onto the sink at x 4; backwards, it is pulled back
+    const CORRIDOR: &str = "######\n#@ $.#\n######\n";
+
+    #[test]
+    fn reverse_solution_replays_forwards() {
+        let mut reverse = game(CORRIDOR).reversed();
+        assert!(reverse.jump_to(&Point { x: 3, y: 1 }));
+        assert!(reverse.try_perform_move(&DIR_LEFT));
+        assert!(reverse.is_game_won());
+        let lurd = reverse.forward_lurd().unwrap();
+        assert_eq!(lurd, "rR");
+        let mut forward = game(CORRIDOR);
+        forward.replay_lurd(&lurd).unwrap();
+        assert!(forward.is_game_won());
+    }
+
+    #[test]
+    fn undo_pull_puts_the_box_back() {
+        let mut reverse = game(CORRIDOR).reversed();
+        assert!(reverse.jump_to(&Point { x: 3, y: 1 }));
+        let before = reverse.state().clone();
+        assert!(reverse.try_perform_move(&DIR_LEFT));
+        assert_eq!(reverse.pushes(), 1);
+        assert!(reverse.undo());
+        assert!(reverse.state() == &before);
+        assert_eq!((reverse.moves(), reverse.pushes()), (0, 0));
+    }
+
+    #[test]
+    fn jump_only_before_the_first_pull() {
+        assert!(!game(CORRIDOR).jump_to(&Point { x: 2, y: 1 }));
+        let mut reverse = game(CORRIDOR).reversed();
+        assert!(!reverse.jump_to(&Point { x: 0, y: 1 }));
+        assert!(reverse.jump_to(&Point { x: 3, y: 1 }));
+        assert!(reverse.try_perform_move(&DIR_LEFT));
+        assert!(!reverse.jump_to(&Point { x: 1, y: 1 }));
+        assert!(reverse.undo());
+        assert!(reverse.jump_to(&Point { x: 1, y: 1 }));
+    }
+    #[test]
+    fn reset_can_be_undone_and_redone() {
+        let mut game = game(CORRIDOR);
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        let played = game.state().clone();
+        assert!(game.reset());
+        assert!(game.state() == game.initial_state());
+        assert_eq!((game.moves(), game.pushes()), (0, 0));
+        assert!(game.undo());
+        assert!(game.state() == &played);
+        assert_eq!((game.moves(), game.pushes()), (2, 1));
+        assert!(game.redo());
+        assert!(game.state() == game.initial_state());
+        assert_eq!((game.moves(), game.pushes()), (0, 0));
+        assert!(!game.redo());
+        assert!(game.undo());
+        assert_eq!(game.lurd(), "rR");
+    }
+    #[test]
+    fn undo_takes_back_a_push() {
+        let mut game = game(CORRIDOR);
+        let start = game.state().clone();
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert_eq!((game.moves(), game.pushes()), (2, 1));
+        assert!(game.undo());
+        assert!(game.state().get_cell(&Point { x: 3, y: 1 }) == GameCell::Box);
+        assert!(game.state().get_cell(&Point { x: 4, y: 1 }) == GameCell::Sink);
+        assert_eq!((game.moves(), game.pushes()), (1, 0));
+        assert!(game.undo());
+        assert!(game.state() == &start);
+        assert!(!game.undo());
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(game.is_game_won());
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut game = game(CORRIDOR);
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(game.undo());
+        assert!(game.undo());
+        assert!(game.try_perform_move(&DIR_RIGHT));
+        assert!(!game.redo());
+        assert_eq!(game.lurd(), "r");
+    }
+    #[test]
+    fn push_path_takes_fewest_pushes() {
+        // the fewest moves (8) take 4 pushes; going around the box first
+        // takes 10 moves but only 2 pushes
+        let game = game("#######\n#  ####\n# #  ##\n##  $@#\n##   .#\n#######\n");
+        let path = game
+            .state()
+            .find_push_path(&Point { x: 4, y: 3 }, &Point { x: 5, y: 4 })
+            .unwrap();
+        let mut state = game.state().clone();
+        let pushes = path
+            .iter()
+            .filter(|direction| state.try_perform_move(direction).unwrap().pushed)
+            .count();
+        assert_eq!((path.len(), pushes), (10, 2));
+        assert!(state.all_boxes_on_sinks());
+    }
+    #[test]
+    fn uppercase_step_without_push_is_rolled_back() {
+        let mut game = game(CORRIDOR);
+        let error = game.replay_lurd("R").unwrap_err();
+        assert_eq!(error.index, 0);
+        assert_eq!(error.kind, LurdErrorKind::WrongCase('r'));
+        assert!(game.state() == game.initial_state());
+        assert_eq!((game.moves(), game.pushes()), (0, 0));
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn lowercase_step_with_push_is_rolled_back() {
+        let mut game = game(CORRIDOR);
+        let error = game.replay_lurd("rr").unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(error.kind, LurdErrorKind::WrongCase('R'));
+        assert_eq!(game.lurd(), "r");
+        assert_eq!((game.moves(), game.pushes()), (1, 0));
+        assert!(game.state().get_cell(&Point { x: 3, y: 1 }) == GameCell::Box);
+        assert!(!game.redo());
     }
 }
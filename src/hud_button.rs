@@ -0,0 +1,59 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Simple clickable buttons for the head-up display; works with mouse and touch,
+// respecting the camera position.
+
+use crate::macroquad_helpers::{FontProvider, get_adjusted_mouse_position};
+use macroquad::prelude::*;
+
+pub struct HudButton<'a> {
+    rect: Rect,
+    label: &'static str,
+    camera: &'a Camera2D,
+    fonts: &'a FontProvider,
+}
+
+impl<'a> HudButton<'a> {
+    pub fn new(
+        camera: &'a Camera2D,
+        rect: Rect,
+        label: &'static str,
+        fonts: &'a FontProvider,
+    ) -> Self {
+        Self {
+            rect,
+            label,
+            camera,
+            fonts,
+        }
+    }
+
+    pub fn draw(&self) {
+        let mouse_world = get_adjusted_mouse_position(self.camera);
+        let color = if self.rect.contains(mouse_world) {
+            GRAY
+        } else {
+            DARKGRAY
+        };
+        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, color);
+        let text_params = TextParams {
+            font: Some(self.fonts.font()),
+            font_size: 28,
+            color: WHITE,
+            ..Default::default()
+        };
+        let text_size = measure_text(self.label, Some(self.fonts.font()), 28, 1.0);
+        draw_text_ex(
+            self.label,
+            self.rect.x + (self.rect.w - text_size.width) / 2.,
+            self.rect.y + (self.rect.h + text_size.offset_y) / 2.,
+            text_params,
+        );
+    }
+
+    // Returns true iff the button was clicked (or tapped) in this frame.
+    pub fn update(&self) -> bool {
+        is_mouse_button_pressed(MouseButton::Left)
+            && self.rect.contains(get_adjusted_mouse_position(self.camera))
+    }
+}
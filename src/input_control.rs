@@ -3,10 +3,16 @@
 // Input control logic for game board, keys or swipe.
 
 use crate::game_logic::Point;
+use crate::macroquad_helpers::get_adjusted_mouse_position;
 use macroquad::prelude::*;
 
 pub struct InputControl {
     touch_start: Option<Vec2>,
+    click_start: Option<Vec2>,
+    // last position of a right or middle button drag
+    drag_position: Option<Vec2>,
+    // center and distance of two fingers in the last frame
+    pinch: Option<(Vec2, f32)>,
 }
 
 pub const DIR_NO_MOVE: Point = Point { x: 0, y: 0 };
@@ -15,10 +21,154 @@ pub const DIR_DOWN: Point = Point { x: 0, y: 1 };
 pub const DIR_RIGHT: Point = Point { x: 1, y: 0 };
 pub const DIR_LEFT: Point = Point { x: -1, y: 0 };
 
+// Change of the board view in this frame, in camera coordinates: zoom by
+// `zoom` around `center` (the board center if None), then move by `pan`.
+pub struct ViewGesture {
+    pub zoom: f32,
+    pub center: Option<Vec2>,
+    pub pan: Vec2,
+    pub reset: bool,
+}
+
+// Game commands that are not a player step.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Undo,
+    Redo,
+    Reset,
+    CopyMoves,
+    PasteMoves,
+    CopyLevel,
+    CopyLevelRle,
+    Solve,
+    Hint,
+    Resume,
+    Edit,
+    Pause,
+    // switch between playing forwards and backwards
+    Reverse,
+}
+
 impl InputControl {
     pub fn new() -> Self {
-        Self { touch_start: None }
+        Self {
+            touch_start: None,
+            click_start: None,
+            drag_position: None,
+            pinch: None,
+        }
+    }
+
+    // Zooming with mouse wheel, +/- keys or pinching, panning by dragging with
+    // the right or middle mouse button or with two fingers; 0 resets the view.
+    // Mouse input is ignored while accept_mouse is false, e.g. if a popup is open.
+    pub fn get_view_gesture(&mut self, camera: &Camera2D, accept_mouse: bool) -> ViewGesture {
+        let position = get_adjusted_mouse_position(camera);
+        let mut gesture = ViewGesture {
+            zoom: 1.,
+            center: None,
+            pan: Vec2::ZERO,
+            reset: is_key_pressed(KeyCode::Key0) || is_key_pressed(KeyCode::Kp0),
+        };
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            gesture.zoom = 1.25;
+        } else if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            gesture.zoom = 0.8;
+        }
+        let wheel = mouse_wheel().1;
+        if accept_mouse && wheel != 0. {
+            gesture.zoom = if wheel > 0. { 1.1 } else { 1. / 1.1 };
+            gesture.center = Some(position);
+        }
+        let dragging =
+            is_mouse_button_down(MouseButton::Right) || is_mouse_button_down(MouseButton::Middle);
+        if !dragging {
+            self.drag_position = None;
+        } else if let Some(last) = self.drag_position {
+            gesture.pan = position - last;
+            self.drag_position = Some(position);
+        } else if accept_mouse {
+            self.drag_position = Some(position);
+        }
+        let fingers: Vec<Vec2> = touches()
+            .iter()
+            .filter(|touch| {
+                touch.phase != TouchPhase::Ended && touch.phase != TouchPhase::Cancelled
+            })
+            .map(|touch| camera.screen_to_world(touch.position))
+            .collect();
+        if let [first, second] = fingers[..] {
+            // two fingers never make a swipe
+            self.touch_start = None;
+            let center = (first + second) / 2.;
+            let distance = first.distance(second);
+            if let Some((last_center, last_distance)) = self.pinch
+                && last_distance > 0.
+            {
+                gesture.zoom = distance / last_distance;
+                gesture.center = Some(center);
+                gesture.pan = center - last_center;
+            }
+            self.pinch = Some((center, distance));
+        } else {
+            self.pinch = None;
+        }
+        gesture
+    }
+    // Returns the command whose key was pressed in this frame, if any.
+    pub fn get_command(&self) -> Option<Command> {
+        if is_key_pressed(KeyCode::U) || is_key_pressed(KeyCode::Z) {
+            return Some(Command::Undo);
+        } else if is_key_pressed(KeyCode::Y) {
+            return Some(Command::Redo);
+        } else if is_key_pressed(KeyCode::R) {
+            return Some(Command::Reset);
+        } else if is_key_pressed(KeyCode::S) {
+            return Some(Command::Solve);
+        } else if is_key_pressed(KeyCode::H) {
+            return Some(Command::Hint);
+        } else if is_key_pressed(KeyCode::Enter) {
+            return Some(Command::Resume);
+        } else if is_key_pressed(KeyCode::E) {
+            return Some(Command::Edit);
+        } else if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::P) {
+            return Some(Command::Pause);
+        } else if is_key_pressed(KeyCode::B) {
+            return Some(Command::Reverse);
+        }
+        let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if control && is_key_pressed(KeyCode::C) {
+            return Some(Command::CopyMoves);
+        } else if control && is_key_pressed(KeyCode::V) {
+            return Some(Command::PasteMoves);
+        } else if control && is_key_pressed(KeyCode::L) {
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            return Some(if shift {
+                Command::CopyLevelRle
+            } else {
+                Command::CopyLevel
+            });
+        }
+        None
     }
+
+    // Returns the (camera adjusted) position of a click or tap that ended in
+    // this frame; presses that moved too far are swipes and don't count.
+    // Presses are ignored while accept_press is false, e.g. if a popup is open.
+    pub fn get_click(&mut self, camera: &Camera2D, accept_press: bool) -> Option<Vec2> {
+        let position = get_adjusted_mouse_position(camera);
+        if is_mouse_button_pressed(MouseButton::Left) && accept_press {
+            self.click_start = Some(position);
+        }
+        if is_mouse_button_released(MouseButton::Left)
+            && let Some(start) = self.click_start.take()
+            && (position - start).length() <= 10.0
+        {
+            return Some(position);
+        }
+        None
+    }
+
     // Returns the direction currently pressed (if any touch or click inside buttons)
     // Returns an Option with Direction enum, or None if no button pressed
     pub fn get_direction(&mut self) -> Point {
@@ -3,14 +3,28 @@
 // Level choCombo box functionality. Macroquad's combobox scales poorly.
 // Chatgpt helped with this component a lot. But it was necessary to adapt to the camera position.
 
+use crate::level_list::draw_solved_mark;
 use crate::macroquad_helpers::{FontProvider, get_adjusted_mouse_position};
 use macroquad::prelude::*;
 
+// number of rows the open dropdown shows at once
+const VISIBLE_ROWS: usize = 7;
+
+enum Row {
+    Item(usize),
+    ScrollUp,
+    ScrollDown,
+}
+
 pub struct LevelChooser<'a> {
     rect: Rect,
-    items: &'static [&'static str],
+    items: Vec<String>,
+    // best score of every solved item
+    scores: Vec<Option<String>>,
     selected: usize,
     is_open: bool,
+    // index of the first item shown in the dropdown
+    scroll: usize,
     camera: &'a Camera2D,
     fonts: &'a FontProvider,
 }
@@ -21,25 +35,64 @@ impl<'a> LevelChooser<'a> {
         x: f32,
         y: f32,
         width: f32,
-        items: &'static [&'static str],
+        items: Vec<String>,
         fonts: &'a FontProvider,
     ) -> Self {
         LevelChooser {
             rect: Rect::new(x, y, width, 80.0),
+            scores: vec![None; items.len()],
             items,
             selected: 0,
             is_open: false,
+            scroll: 0,
             camera,
             fonts,
         }
     }
 
+    // The rows of the open dropdown; long lists get rows for scrolling.
+    fn rows(&self) -> Vec<Row> {
+        if self.items.len() <= VISIBLE_ROWS {
+            return (0..self.items.len()).map(Row::Item).collect();
+        }
+        let mut rows = Vec::new();
+        if self.scroll > 0 {
+            rows.push(Row::ScrollUp);
+        }
+        let item_rows = VISIBLE_ROWS - 2;
+        let end = (self.scroll + item_rows).min(self.items.len());
+        rows.extend((self.scroll..end).map(Row::Item));
+        if end < self.items.len() {
+            rows.push(Row::ScrollDown);
+        }
+        rows
+    }
+
+    pub fn set_selected(&mut self, index: usize) {
+        self.selected = index;
+    }
+
+    // Mark an item as solved, showing a checkmark and the given score.
+    pub fn set_solved(&mut self, index: usize, score: String) {
+        self.scores[index] = Some(score);
+    }
+
+    fn row_rect(&self, row_idx: usize) -> Rect {
+        let y = self.rect.y + self.rect.h * (row_idx as f32 + 1.0);
+        Rect::new(self.rect.x, y, self.rect.w, self.rect.h)
+    }
+
+    fn scroll_by(&mut self, rows: isize) {
+        let max_scroll = self.items.len().saturating_sub(VISIBLE_ROWS - 2);
+        self.scroll = self.scroll.saturating_add_signed(rows).min(max_scroll);
+    }
+
     pub fn draw(&self) {
         // Draw the main box
         draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, DARKGRAY);
         let text_params = TextParams {
             font: Some(self.fonts.font()),
-            font_size: 68,
+            font_size: 40,
             color: WHITE,
             ..Default::default()
         };
@@ -60,9 +113,8 @@ impl<'a> LevelChooser<'a> {
 
         // If open, draw the dropdown items below
         if self.is_open {
-            for (i, item) in self.items.iter().enumerate() {
-                let y = self.rect.y + self.rect.h * (i as f32 + 1.0);
-                let item_rect = Rect::new(self.rect.x, y, self.rect.w, self.rect.h);
+            for (row_idx, row) in self.rows().iter().enumerate() {
+                let item_rect = self.row_rect(row_idx);
 
                 // Highlight on hover
                 if item_rect.contains(mouse_world) {
@@ -71,41 +123,70 @@ impl<'a> LevelChooser<'a> {
                     draw_rectangle(item_rect.x, item_rect.y, item_rect.w, item_rect.h, DARKGRAY);
                 }
 
-                draw_text_ex(
-                    item,
-                    item_rect.x + 5.0,
-                    item_rect.y + 60.0,
-                    text_params.clone(),
-                );
+                let text_x = item_rect.x + 5.0;
+                let text_y = item_rect.y + 55.0;
+                let i = match row {
+                    Row::Item(i) => *i,
+                    Row::ScrollUp => {
+                        draw_text_ex("▲", text_x, text_y, symbol_text_params.clone());
+                        continue;
+                    }
+                    Row::ScrollDown => {
+                        draw_text_ex("▼", text_x, text_y, symbol_text_params.clone());
+                        continue;
+                    }
+                };
+                // leave room for the checkmark
+                draw_text_ex(&self.items[i], text_x + 40.0, text_y, text_params.clone());
+                if let Some(score) = &self.scores[i] {
+                    draw_solved_mark(score, item_rect, text_y, self.fonts);
+                }
             }
         }
     }
 
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
     pub fn update(&mut self) -> Option<usize> {
         let mouse_pos = get_adjusted_mouse_position(self.camera);
 
+        if self.is_open {
+            let wheel = mouse_wheel().1;
+            if wheel > 0.0 {
+                self.scroll_by(-1);
+            } else if wheel < 0.0 {
+                self.scroll_by(1);
+            }
+        }
+
         if is_mouse_button_pressed(MouseButton::Left) {
             let mouse_pt = Vec2::new(mouse_pos.x, mouse_pos.y);
 
             if self.rect.contains(mouse_pt) {
                 self.is_open = !self.is_open;
+                // show the selected item when opening
+                self.scroll = 0;
+                self.scroll_by(self.selected as isize - 1);
                 return None;
             }
 
             if self.is_open {
-                for i in 0..self.items.len() {
-                    let item_rect = Rect::new(
-                        self.rect.x,
-                        self.rect.y + self.rect.h * (i as f32 + 1.0),
-                        self.rect.w,
-                        self.rect.h,
-                    );
-
-                    if item_rect.contains(mouse_pt) {
-                        self.selected = i;
-                        self.is_open = false;
-                        return Some(i);
+                for (row_idx, row) in self.rows().into_iter().enumerate() {
+                    if !self.row_rect(row_idx).contains(mouse_pt) {
+                        continue;
+                    }
+                    match row {
+                        Row::Item(i) => {
+                            self.selected = i;
+                            self.is_open = false;
+                            return Some(i);
+                        }
+                        Row::ScrollUp => self.scroll_by(-(VISIBLE_ROWS as isize - 3)),
+                        Row::ScrollDown => self.scroll_by(VISIBLE_ROWS as isize - 3),
                     }
+                    return None;
                 }
                 self.is_open = false;
             }
@@ -0,0 +1,276 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Level editor: paint tiles from a palette onto a resizable grid, test play
+// the level and save it as XSB file.
+
+use macroquad::prelude::*;
+
+use crate::animation::BoardAnimation;
+use crate::game_board::GameBoard;
+use crate::game_logic::{Game, GameCell, GameState, Point};
+use crate::hud_button::HudButton;
+use crate::level_writer::level_to_string;
+use crate::macroquad_helpers::{FontProvider, get_adjusted_mouse_position};
+
+const MIN_SIZE: u16 = 3;
+const MAX_WIDTH: u16 = 40;
+const MAX_HEIGHT: u16 = 25;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Box,
+    Sink,
+    BoxOnSink,
+    Player,
+}
+
+// palette entries with the sprite shown for them
+const PALETTE: [(Tile, GameCell); 6] = [
+    (Tile::Wall, GameCell::Unmovable),
+    (Tile::Floor, GameCell::Empty),
+    (Tile::Box, GameCell::Box),
+    (Tile::Sink, GameCell::Sink),
+    (Tile::BoxOnSink, GameCell::SinkWithBox),
+    (Tile::Player, GameCell::Player),
+];
+
+pub enum EditorAction {
+    TestPlay,
+    Save,
+    Done,
+}
+
+pub struct LevelEditor<'a> {
+    state: GameState,
+    tile: Tile,
+    // a press that started on the board paints until it is released
+    painting: bool,
+    narrower_button: HudButton<'a>,
+    wider_button: HudButton<'a>,
+    shorter_button: HudButton<'a>,
+    taller_button: HudButton<'a>,
+    test_button: HudButton<'a>,
+    save_button: HudButton<'a>,
+    done_button: HudButton<'a>,
+    camera: &'a Camera2D,
+    fonts: &'a FontProvider,
+}
+
+impl<'a> LevelEditor<'a> {
+    // Start editing the given level; an empty state gives a walled room.
+    pub fn new(camera: &'a Camera2D, state: GameState, fonts: &'a FontProvider) -> Self {
+        let state = if state.width() == 0 || state.height() == 0 {
+            empty_room(10, 8)
+        } else {
+            state
+        };
+        let button =
+            |x: f32, w: f32, label| HudButton::new(camera, Rect::new(x, 2., w, 80.), label, fonts);
+        Self {
+            state,
+            tile: Tile::Wall,
+            painting: false,
+            narrower_button: button(660., 60., "W-"),
+            wider_button: button(725., 60., "W+"),
+            shorter_button: button(790., 60., "H-"),
+            taller_button: button(855., 60., "H+"),
+            test_button: button(925., 110., "Test"),
+            save_button: button(1040., 110., "Save"),
+            done_button: button(1155., 110., "Done"),
+            camera,
+            fonts,
+        }
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    fn palette_rect(index: usize) -> Rect {
+        Rect::new(230. + index as f32 * 70., 10., 64., 64.)
+    }
+
+    fn resize(&mut self, width: i32, height: i32) {
+        let width = width.clamp(MIN_SIZE as i32, MAX_WIDTH as i32) as u16;
+        let height = height.clamp(MIN_SIZE as i32, MAX_HEIGHT as i32) as u16;
+        self.state = self.state.resized(width, height);
+        // the player may have been moved onto a wall or box
+        let player = self.state.get_player_position();
+        match self.state.get_cell(&player) {
+            GameCell::Empty | GameCell::Sink => {}
+            GameCell::SinkWithBox => self.state.set_cell(&player, GameCell::Sink),
+            _ => self.state.set_cell(&player, GameCell::Empty),
+        }
+    }
+
+    // Put the selected tile on a cell. The player can only be moved, so other
+    // tiles that would cover it are not placed.
+    fn paint(&mut self, pos: &Point) {
+        let on_player = *pos == self.state.get_player_position();
+        let cell = match self.tile {
+            Tile::Wall => GameCell::Unmovable,
+            Tile::Floor => GameCell::Empty,
+            Tile::Box => GameCell::Box,
+            Tile::Sink => GameCell::Sink,
+            Tile::BoxOnSink => GameCell::SinkWithBox,
+            Tile::Player => {
+                // keep a sink below the player
+                let cell = match self.state.get_cell(pos) {
+                    GameCell::Sink | GameCell::SinkWithBox => GameCell::Sink,
+                    _ => GameCell::Empty,
+                };
+                self.state.set_cell(pos, cell);
+                self.state.set_player_position(pos);
+                return;
+            }
+        };
+        if !on_player || cell == GameCell::Empty || cell == GameCell::Sink {
+            self.state.set_cell(pos, cell);
+        }
+    }
+
+    pub fn update(&mut self, game_board: &GameBoard) -> Option<EditorAction> {
+        let (width, height) = (self.state.width(), self.state.height());
+        if self.narrower_button.update() {
+            self.resize(width - 1, height);
+        } else if self.wider_button.update() {
+            self.resize(width + 1, height);
+        } else if self.shorter_button.update() {
+            self.resize(width, height - 1);
+        } else if self.taller_button.update() {
+            self.resize(width, height + 1);
+        } else if self.test_button.update() {
+            return Some(EditorAction::TestPlay);
+        } else if self.save_button.update() {
+            return Some(EditorAction::Save);
+        } else if self.done_button.update() {
+            return Some(EditorAction::Done);
+        }
+        let position = get_adjusted_mouse_position(self.camera);
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(index) =
+                (0..PALETTE.len()).find(|i| Self::palette_rect(*i).contains(position))
+            {
+                self.tile = PALETTE[index].0;
+            }
+            self.painting = game_board.contains(position);
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.painting = false;
+        }
+        if self.painting
+            && let Some(pos) = game_board.cell_at(&self.state, position)
+        {
+            self.paint(&pos);
+        }
+        None
+    }
+
+    pub fn draw(&self, game_board: &GameBoard, notice: Option<&str>) {
+        game_board.draw_board(&self.state, &[], &BoardAnimation::new(0.));
+        game_board.draw_margins();
+        if let Some(notice) = notice {
+            draw_text_ex(
+                notice,
+                230.,
+                100.,
+                TextParams {
+                    font: Some(self.fonts.font()),
+                    font_size: 22,
+                    color: MAROON,
+                    ..Default::default()
+                },
+            );
+        }
+        draw_text_ex(
+            "Editor",
+            20.,
+            38.,
+            TextParams {
+                font: Some(self.fonts.font()),
+                font_size: 34,
+                color: DARKGRAY,
+                ..Default::default()
+            },
+        );
+        let size = format!("{} x {}", self.state.width(), self.state.height());
+        draw_text_ex(
+            &size,
+            20.,
+            74.,
+            TextParams {
+                font: Some(self.fonts.font()),
+                font_size: 28,
+                color: BLUE,
+                ..Default::default()
+            },
+        );
+        for (index, (tile, sprite)) in PALETTE.iter().enumerate() {
+            let rect = Self::palette_rect(index);
+            game_board.draw_tile(*sprite, rect);
+            if *tile == self.tile {
+                draw_rectangle_lines(rect.x - 3., rect.y - 3., rect.w + 6., rect.h + 6., 6., GOLD);
+            }
+        }
+        self.narrower_button.draw();
+        self.wider_button.draw();
+        self.shorter_button.draw();
+        self.taller_button.draw();
+        self.test_button.draw();
+        self.save_button.draw();
+        self.done_button.draw();
+    }
+}
+
+// A room of the given size surrounded by walls, the player in the top left corner.
+fn empty_room(width: u16, height: u16) -> GameState {
+    let mut state = GameState::new(width, height);
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            if x == 0 || y == 0 || x == width as i32 - 1 || y == height as i32 - 1 {
+                state.set_cell(&Point { x, y }, GameCell::Unmovable);
+            }
+        }
+    }
+    state.set_player_position(&Point { x: 1, y: 1 });
+    state
+}
+
+// Store the level as the first free levels/custom_<n>.lvl and put it on the
+// clipboard; on the web, where files can't be written, only the latter.
+// Returns a notice for the player.
+pub fn save_level(state: &GameState) -> String {
+    let mut game = Game::new(state.clone(), String::new(), String::new());
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let Some((number, path)) = (1..)
+            .map(|n| (n, format!("levels/custom_{n}.lvl")))
+            .find(|(_, path)| !std::path::Path::new(path).exists())
+        else {
+            return "No free file name".to_owned();
+        };
+        game.set_title(format!("Custom level {number}"));
+        let contents = match level_to_string(&game) {
+            Ok(contents) => contents,
+            Err(error) => return format!("Can't save level: {error}"),
+        };
+        macroquad::miniquad::window::clipboard_set(&contents);
+        match std::fs::write(&path, contents) {
+            Ok(()) => format!("Saved to {path} and copied to clipboard"),
+            Err(error) => format!("Can't save {path}: {error}"),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        game.set_title("Custom level".to_owned());
+        match level_to_string(&game) {
+            Ok(contents) => {
+                macroquad::miniquad::window::clipboard_set(&contents);
+                "Level copied to clipboard".to_owned()
+            }
+            Err(error) => format!("Can't save level: {error}"),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// kanjiban
+// (C) 2025 by JoAn
+// The levels the game offers, as listed in the manifest, and loading them.
+
+use macroquad::prelude::*;
+
+use crate::game_logic::{Game, GameState};
+use crate::level_loader::{LevelLoader, LevelParseError, LevelParseErrorKind, load_level_file};
+use crate::level_manifest::{
+    MANIFEST_PATH, ManifestEntry, ManifestError, manifest_file_path, parse_manifest,
+};
+use crate::level_validator::{Diagnostic, validate_state};
+use crate::macroquad_helpers::FontProvider;
+use crate::save_data::LevelRecord;
+
+// Where a level offered by the level chooser comes from.
+pub enum LevelSource {
+    File(String),
+    // path of a collection file and index of the level within it
+    Collection(String, usize),
+}
+
+impl LevelSource {
+    // Identifies the level in the save data.
+    pub fn key(&self) -> String {
+        match self {
+            LevelSource::File(path) => path.clone(),
+            LevelSource::Collection(path, index) => format!("{path}#{}", index + 1),
+        }
+    }
+}
+
+pub struct LevelEntry {
+    pub label: String,
+    pub source: LevelSource,
+}
+
+pub fn score_text(record: &LevelRecord) -> String {
+    format!("{}/{}", record.moves, record.pushes)
+}
+
+// Mark a row of a level list as solved: a checkmark on the left, where the
+// label leaves room for it, and the best score on the right. y is the
+// baseline of the row's text.
+pub fn draw_solved_mark(score: &str, row: Rect, y: f32, fonts: &FontProvider) {
+    draw_text_ex(
+        "✓",
+        row.x + 8.,
+        y,
+        TextParams {
+            font: Some(fonts.symbol_font()),
+            font_size: 40,
+            color: GREEN,
+            ..Default::default()
+        },
+    );
+    let params = TextParams {
+        font: Some(fonts.font()),
+        font_size: 26,
+        color: LIGHTGRAY,
+        ..Default::default()
+    };
+    let width = measure_text(score, params.font, params.font_size, 1.0).width;
+    draw_text_ex(score, row.right() - width - 10., y, params);
+}
+
+// The levels listed in the manifest; collections contribute all their levels.
+// Collections that can't be read are left out.
+pub async fn level_entries() -> Result<Vec<LevelEntry>, ManifestError> {
+    let contents = load_level_file(MANIFEST_PATH)
+        .await
+        .ok_or_else(|| ManifestError {
+            line: 0,
+            message: format!("Level list {MANIFEST_PATH} not found"),
+        })?;
+    let mut entries = Vec::new();
+    for entry in parse_manifest(&contents)? {
+        match entry {
+            ManifestEntry::Level { file, title } => entries.push(LevelEntry {
+                label: title.unwrap_or_else(|| {
+                    file.rsplit_once('.')
+                        .map_or(file.as_str(), |(stem, _)| stem)
+                        .to_owned()
+                }),
+                source: LevelSource::File(manifest_file_path(&file)),
+            }),
+            ManifestEntry::Collection { file } => {
+                let path = manifest_file_path(&file);
+                match LevelLoader::new(&path).parse_collection().await {
+                    Ok(collection) => {
+                        entries.extend(collection.levels.iter().enumerate().map(|(i, game)| {
+                            LevelEntry {
+                                label: game.title().to_owned(),
+                                source: LevelSource::Collection(path.clone(), i),
+                            }
+                        }))
+                    }
+                    Err(error) => warn!("{}: {}", path, error),
+                }
+            }
+        }
+    }
+    if entries.is_empty() {
+        return Err(ManifestError {
+            line: 0,
+            message: "None of the listed collections could be read".to_owned(),
+        });
+    }
+    Ok(entries)
+}
+
+// Load a level and run the validator on it.
+pub async fn load_level(source: &LevelSource) -> Result<(Game, Vec<Diagnostic>), LevelParseError> {
+    match source {
+        LevelSource::File(path) => LevelLoader::new(path).parse_and_validate_level().await,
+        LevelSource::Collection(path, index) => {
+            let collection = LevelLoader::new(path).parse_collection().await?;
+            let mut game = collection
+                .levels
+                .into_iter()
+                .nth(*index)
+                .ok_or(LevelParseError::whole_level(LevelParseErrorKind::NoBoard))?;
+            // show which collection the level belongs to
+            if !collection.title.is_empty() {
+                game.set_title(format!("{}: {}", collection.title, game.title()));
+            }
+            if game.author().is_empty() {
+                game.set_author(collection.copyright);
+            }
+            let diagnostics = validate_state(game.state());
+            Ok((game, diagnostics))
+        }
+    }
+}
+
+// Load a level; if that fails, an empty game is returned along with the error.
+pub async fn load_level_or_empty(
+    source: &LevelSource,
+) -> (Game, Option<LevelParseError>, Vec<Diagnostic>) {
+    match load_level(source).await {
+        Ok((game, diagnostics)) => {
+            for diagnostic in &diagnostics {
+                warn!("{}", diagnostic);
+            }
+            (game, None, diagnostics)
+        }
+        Err(error) => (
+            Game::new(GameState::new(0, 0), String::new(), String::new()),
+            Some(error),
+            Vec::new(),
+        ),
+    }
+}
@@ -2,10 +2,236 @@
 // (C) 2025 by JoAn
 // Parser for Sokoban levels
 use crate::game_logic::{Game, GameCell, GameState, Point};
+use crate::level_validator::{Diagnostic, validate_level};
+use crate::rle::{RleError, decode_rle, is_rle_char};
 
 use macroquad::prelude::*;
+use std::fmt;
 
 const ALLOWED_BOARD_CHARS: &str = "#pPbB._ -.$*+@";
+// most cells a board may have; larger boards are reported, not allocated
+const MAX_BOARD_CELLS: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelParseErrorKind {
+    MissingFile(String),
+    NoBoard,
+    UnknownCharacter(char),
+    MissingPlayer,
+    MissingBoxes,
+    MalformedXml(String),
+    Rle(RleError),
+    // width and height of the board
+    BoardTooLarge(usize, usize),
+}
+
+// Line and column are 1-based; both are 0 if the error concerns the whole level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: LevelParseErrorKind,
+}
+
+impl LevelParseError {
+    pub fn whole_level(kind: LevelParseErrorKind) -> Self {
+        Self {
+            line: 0,
+            column: 0,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for LevelParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LevelParseErrorKind::MissingFile(path) => write!(f, "Level file {path} not found")?,
+            LevelParseErrorKind::NoBoard => write!(f, "Level contains no game board")?,
+            LevelParseErrorKind::UnknownCharacter(c) => write!(f, "Unknown board character '{c}'")?,
+            LevelParseErrorKind::MissingPlayer => write!(f, "Level has no player")?,
+            LevelParseErrorKind::MissingBoxes => write!(f, "Level has no boxes")?,
+            LevelParseErrorKind::MalformedXml(message) => write!(f, "Malformed XML: {message}")?,
+            LevelParseErrorKind::Rle(error) => write!(f, "{error}")?,
+            LevelParseErrorKind::BoardTooLarge(width, height) => {
+                write!(f, "Game board of {width}x{height} cells is too large")?
+            }
+        }
+        if self.line > 0 {
+            write!(f, " (line {}, column {})", self.line, self.column)?;
+        }
+        Ok(())
+    }
+}
+
+// A single level read from text, with what the validator checks beyond the game.
+pub struct ParsedLevel {
+    pub game: Game,
+    // player characters on the board; the last one is where the player starts
+    pub players: usize,
+    // keys of the "Key: value" lines after the board, with their line numbers
+    pub info_keys: Vec<(usize, String)>,
+}
+
+// Levels read from one file, along with the information about the whole set.
+pub struct LevelCollection {
+    pub title: String,
+    pub copyright: String,
+    pub levels: Vec<Game>,
+}
+
+// A start or end tag of an XML document.
+struct XmlTag<'a> {
+    name: &'a str,
+    attributes: &'a str,
+    closing: bool,
+    self_closing: bool,
+    // byte offsets of '<' and after '>'
+    start: usize,
+    end: usize,
+}
+
+// All tags of an XML document, skipping declarations, comments and CDATA
+// sections; the latter are left to the text between the tags.
+fn xml_tags(xml: &str) -> Vec<XmlTag<'_>> {
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = xml[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &xml[start..];
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else {
+            ">"
+        };
+        let Some(length) = rest.find(terminator) else {
+            break;
+        };
+        let end = start + length + terminator.len();
+        pos = end;
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            continue;
+        }
+        let inner = &xml[start + 1..end - 1];
+        let closing = inner.starts_with('/');
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_start_matches('/').trim_end_matches('/');
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        tags.push(XmlTag {
+            name: &inner[..name_end],
+            attributes: &inner[name_end..],
+            closing,
+            self_closing,
+            start,
+            end,
+        });
+    }
+    tags
+}
+
+// Value of an attribute like Id="12" in the attribute part of a tag.
+fn xml_attribute(attributes: &str, name: &str) -> Result<Option<String>, LevelParseErrorKind> {
+    let mut rest = attributes;
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            return Err(LevelParseErrorKind::MalformedXml(format!(
+                "value of {key} is not quoted"
+            )));
+        };
+        let Some(value_end) = value[1..].find(quote).map(|end| end + 1) else {
+            return Err(LevelParseErrorKind::MalformedXml(format!(
+                "value of {key} is not closed"
+            )));
+        };
+        if key == name {
+            return Ok(Some(xml_unescape(&value[1..value_end])));
+        }
+        rest = &value[value_end + 1..];
+    }
+    Ok(None)
+}
+
+// Decode the predefined entities and character references like &#233; or
+// &#xE9;; anything else starting with '&' is kept as it is.
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(ampersand) = rest.find('&') {
+        unescaped += &rest[..ampersand];
+        rest = &rest[ampersand..];
+        let decoded = rest.find(';').and_then(|semicolon| {
+            let c = match &rest[1..semicolon] {
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "amp" => '&',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#')?.parse().ok(),
+                    };
+                    char::from_u32(code?)?
+                }
+            };
+            Some((c, semicolon + 1))
+        });
+        match decoded {
+            Some((c, length)) => {
+                unescaped.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped + rest
+}
+
+// Text between tags: entities are decoded, CDATA sections taken as they are.
+fn xml_text(raw: &str) -> String {
+    let mut text = String::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("<![CDATA[") {
+        text += &xml_unescape(&rest[..start]);
+        let section = &rest[start + "<![CDATA[".len()..];
+        let end = section.find("]]>").unwrap_or(section.len());
+        text += &section[..end];
+        rest = &section[(end + "]]>".len()).min(section.len())..];
+    }
+    text + &xml_unescape(rest)
+}
+
+fn xml_error(contents: &str, byte_offset: usize, kind: LevelParseErrorKind) -> LevelParseError {
+    let line_start = contents[..byte_offset].rfind('\n').map_or(0, |i| i + 1);
+    LevelParseError {
+        line: line_number(contents, byte_offset),
+        column: contents[line_start..byte_offset].chars().count() + 1,
+        kind,
+    }
+}
+
+fn line_number(contents: &str, byte_offset: usize) -> usize {
+    contents[..byte_offset].matches('\n').count() + 1
+}
+
+// Load a file of the levels directory; with the "embedded-levels" feature, the
+// copy compiled into the binary is used if the file can't be loaded.
+pub async fn load_level_file(path: &str) -> Option<String> {
+    match load_string(path).await {
+        Ok(contents) => Some(contents),
+        #[cfg(feature = "embedded-levels")]
+        Err(_) => crate::embedded_levels::embedded_file(path).map(String::from),
+        #[cfg(not(feature = "embedded-levels"))]
+        Err(_) => None,
+    }
+}
 
 enum ParseState {
     Start,
@@ -22,50 +248,308 @@ impl LevelLoader {
         }
     }
 
-    // returns true iff only characters for describing board content are contained in line.
-    fn is_level_line(&self, line: &str) -> bool {
-        !line.chars().any(|c| !ALLOWED_BOARD_CHARS.contains(c))
+    // returns true iff the line starts the additional info (title, author, ...) after the board.
+    pub fn is_info_line(line: &str) -> bool {
+        line.contains(':')
+    }
+
+    // returns true iff the line only consists of board content, possibly
+    // run-length encoded.
+    fn is_board_syntax(line: &str) -> bool {
+        line.chars()
+            .all(|c| ALLOWED_BOARD_CHARS.contains(c) || is_rle_char(c))
+    }
+
+    // returns true iff the line ends the board: the additional info, or, once
+    // the board has begun, a comment with text that isn't board content.
+    // Rows starting with a wall are kept, so typos in them are still reported.
+    fn ends_board(line: &str, board_begun: bool) -> bool {
+        Self::is_info_line(line)
+            || (board_begun && !line.trim_start().starts_with('#') && !Self::is_board_syntax(line))
+    }
+
+    // returns true iff the line only describes board content (possibly run-length
+    // encoded) and contains a wall.
+    fn is_board_line(line: &str) -> bool {
+        line.contains('#') && Self::is_board_syntax(line)
+    }
+
+    // A row of a board given row by row; empty rows would be skipped by the
+    // parser, so they are kept as a single floor cell.
+    fn board_row(row: &str) -> String {
+        if row.is_empty() {
+            String::from(" ")
+        } else {
+            String::from(row)
+        }
+    }
+
+    // The lines of a level along with their 1-based line number, and how many
+    // of them belong to the board; run-length encoded board lines are expanded
+    // into one line per row.
+    fn expand_lines(contents: &str) -> Result<(Vec<(usize, String)>, usize), LevelParseError> {
+        let mut lines = Vec::new();
+        let mut board_end = None;
+        let mut board_begun = false;
+        for (line_idx, line) in contents.lines().enumerate() {
+            if board_end.is_none() && Self::ends_board(line, board_begun) {
+                board_end = Some(lines.len());
+            }
+            board_begun = board_begun || !line.trim().is_empty();
+            if board_end.is_none() && line.chars().any(is_rle_char) {
+                let decoded = decode_rle(line).map_err(|error| LevelParseError {
+                    line: line_idx + 1,
+                    column: match error {
+                        RleError::CountTooLarge(index) => index + 1,
+                        RleError::TooLong => 1,
+                    },
+                    kind: LevelParseErrorKind::Rle(error),
+                })?;
+                lines.extend(
+                    decoded
+                        .split('\n')
+                        .map(|row| (line_idx + 1, Self::board_row(row))),
+                );
+            } else {
+                lines.push((line_idx + 1, String::from(line)));
+            }
+        }
+        let board_end = board_end.unwrap_or(lines.len());
+        Ok((lines, board_end))
+    }
+
+    async fn load_contents(&self) -> Result<String, LevelParseError> {
+        load_level_file(&self.level_path).await.ok_or_else(|| {
+            LevelParseError::whole_level(LevelParseErrorKind::MissingFile(self.level_path.clone()))
+        })
     }
 
-    pub async fn parse_level(&self) -> Game {
-        // TODO: better error handling
-        let contents = load_string(&self.level_path).await.unwrap();
+    // Parse the level and run the semantic checks of the validator on it.
+    pub async fn parse_and_validate_level(
+        &self,
+    ) -> Result<(Game, Vec<Diagnostic>), LevelParseError> {
+        let contents = self.load_contents().await?;
+        let level = Self::parse_level(&contents)?;
+        let diagnostics = validate_level(&level);
+        Ok((level.game, diagnostics))
+    }
 
+    // Read a level collection, either as text pack or in SLC (XML) format.
+    pub async fn parse_collection(&self) -> Result<LevelCollection, LevelParseError> {
+        let contents = self.load_contents().await?;
+        if contents.trim_start().starts_with('<') {
+            Self::parse_slc_str(&contents)
+        } else {
+            Self::parse_collection_str(&contents)
+        }
+    }
+
+    // Parse an SLC file: <SokobanLevels> with a <Title> and a <LevelCollection>
+    // of <Level Id=".."> elements, each holding one <L> element per board row.
+    // The level ID becomes the title, a level's copyright its author.
+    pub fn parse_slc_str(contents: &str) -> Result<LevelCollection, LevelParseError> {
+        let mut collection = LevelCollection {
+            title: String::new(),
+            copyright: String::new(),
+            levels: Vec::new(),
+        };
+        let tags = xml_tags(contents);
+        let text_after = |tag_idx: usize| -> &str {
+            match tags.get(tag_idx + 1) {
+                Some(next) if next.closing && next.name == tags[tag_idx].name => {
+                    &contents[tags[tag_idx].end..next.start]
+                }
+                _ => "",
+            }
+        };
+        let attribute = |tag: &XmlTag, name| {
+            xml_attribute(tag.attributes, name).map_err(|kind| xml_error(contents, tag.start, kind))
+        };
+        let mut in_level = false;
+        let mut level_id = String::new();
+        let mut level_copyright = String::new();
+        // board rows with the line they were found on
+        let mut rows: Vec<(String, usize)> = Vec::new();
+        for (tag_idx, tag) in tags.iter().enumerate() {
+            match (tag.name, tag.closing) {
+                ("Title", false) if !in_level && collection.title.is_empty() => {
+                    collection.title = xml_text(text_after(tag_idx)).trim().to_owned();
+                }
+                ("LevelCollection", false) => {
+                    collection.copyright = attribute(tag, "Copyright")?.unwrap_or_default();
+                }
+                ("Level", false) => {
+                    in_level = true;
+                    level_id = attribute(tag, "Id")?.unwrap_or_default();
+                    level_copyright = attribute(tag, "Copyright")?
+                        .unwrap_or_else(|| collection.copyright.clone());
+                    rows.clear();
+                }
+                ("L", false) if in_level => {
+                    let row = if tag.self_closing {
+                        ""
+                    } else {
+                        text_after(tag_idx)
+                    };
+                    rows.push((xml_text(row), line_number(contents, tag.start)));
+                }
+                ("Level", true) if in_level => {
+                    in_level = false;
+                    let board: Vec<String> =
+                        rows.iter().map(|(row, _)| Self::board_row(row)).collect();
+                    let mut game = Self::parse_str(&board.join("\n")).map_err(|mut error| {
+                        if error.line > 0 {
+                            error.line = rows[error.line - 1].1;
+                        }
+                        error
+                    })?;
+                    game.set_title(if level_id.is_empty() {
+                        format!("Level {}", collection.levels.len() + 1)
+                    } else {
+                        level_id.clone()
+                    });
+                    game.set_author(level_copyright.clone());
+                    collection.levels.push(game);
+                }
+                _ => {}
+            }
+        }
+        if collection.levels.is_empty() {
+            return Err(LevelParseError::whole_level(LevelParseErrorKind::NoBoard));
+        }
+        Ok(collection)
+    }
+
+    // Parse a collection of levels (.sok / .txt packs): boards separated by
+    // other lines, each one optionally followed by "Key: value" lines. A text
+    // line right above a board (like "; 12") names the level if it has no title.
+    // The first text line of the file is the title of the collection, a
+    // "Copyright: .." line before the first level its copyright.
+    pub fn parse_collection_str(contents: &str) -> Result<LevelCollection, LevelParseError> {
+        let lines: Vec<&str> = contents.lines().collect();
+        let mut levels = Vec::new();
+        let mut current_line_idx = 0;
+        let mut title = "";
+        let mut copyright = "";
+        let first_board = lines
+            .iter()
+            .position(|line| Self::is_board_line(line))
+            .unwrap_or(lines.len());
+        for (line_idx, line) in lines[..first_board].iter().enumerate() {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("Copyright:") {
+                copyright = rest.trim();
+            } else if title.is_empty()
+                && !line.is_empty()
+                && !line.starts_with(';')
+                && !Self::is_info_line(line)
+                && line_idx + 1 != first_board
+            {
+                title = line;
+            }
+        }
+        while current_line_idx < lines.len() {
+            if !Self::is_board_line(lines[current_line_idx]) {
+                current_line_idx += 1;
+                continue;
+            }
+            let start = current_line_idx;
+            while current_line_idx < lines.len() && Self::is_board_line(lines[current_line_idx]) {
+                current_line_idx += 1;
+            }
+            // the info lines of this level, possibly separated by blank lines
+            let mut end = current_line_idx;
+            while current_line_idx < lines.len() && !Self::is_board_line(lines[current_line_idx]) {
+                let line = lines[current_line_idx];
+                if Self::is_info_line(line) {
+                    end = current_line_idx + 1;
+                } else if !line.trim().is_empty() {
+                    break;
+                }
+                current_line_idx += 1;
+            }
+            current_line_idx = end;
+            let mut game =
+                Self::parse_str(&lines[start..end].join("\n")).map_err(|mut error| {
+                    if error.line > 0 {
+                        error.line += start;
+                    }
+                    error
+                })?;
+            if game.title().is_empty() {
+                let name = start
+                    .checked_sub(1)
+                    .map(|i| lines[i].trim_start_matches(';').trim())
+                    .filter(|name| !name.is_empty() && !Self::is_info_line(name));
+                game.set_title(match name {
+                    Some(name) => String::from(name),
+                    None => format!("Level {}", levels.len() + 1),
+                });
+            }
+            levels.push(game);
+        }
+        if levels.is_empty() {
+            return Err(LevelParseError::whole_level(LevelParseErrorKind::NoBoard));
+        }
+        Ok(LevelCollection {
+            title: String::from(title),
+            copyright: String::from(copyright),
+            levels,
+        })
+    }
+
+    // Parse a single level given as text: the board, followed by "Key: value"
+    // lines; other text after the board is taken as a comment.
+    pub fn parse_str(contents: &str) -> Result<Game, LevelParseError> {
+        Self::parse_level(contents).map(|level| level.game)
+    }
+
+    // Like parse_str, along with the details for the validator.
+    pub fn parse_level(contents: &str) -> Result<ParsedLevel, LevelParseError> {
         let mut current_line_idx = 0;
-        assert!(!contents.is_empty());
         let mut parse_state = ParseState::Start;
         let mut y_pos: i32 = 0;
         let mut x_pos: i32 = 0;
-        let lines: Vec<&str> = contents.lines().collect();
+        let (lines, board_end) = Self::expand_lines(contents)?;
         let mut width: usize = 0;
         let mut height: usize = 0;
         // peek to get the dimensions of the game board
-        for line in &lines {
+        for (_, line) in &lines[..board_end] {
             if line.is_empty() {
                 continue;
             }
-            if line.contains(':') {
-                break;
-            }
             height += 1;
-            width = width.max(line.len());
+            width = width.max(line.chars().count());
+        }
+        if height == 0 || width == 0 {
+            return Err(LevelParseError::whole_level(LevelParseErrorKind::NoBoard));
         }
-        assert!(height > 0);
-        assert!(width > 0);
-        let mut game_state = GameState::new(width as u16, height as u16);
+        let too_large =
+            LevelParseError::whole_level(LevelParseErrorKind::BoardTooLarge(width, height));
+        let (Ok(board_width), Ok(board_height)) = (u16::try_from(width), u16::try_from(height))
+        else {
+            return Err(too_large);
+        };
+        if width * height > MAX_BOARD_CELLS {
+            return Err(too_large);
+        }
+        let mut game_state = GameState::new(board_width, board_height);
+        let mut players = 0;
+        let mut info_keys = Vec::new();
+        let mut boxes_found = false;
         let mut title = "";
         let mut author = "";
         // actually parse the lines of the game board and
         // the additional info
         while current_line_idx < lines.len() {
-            let line = lines[current_line_idx];
+            let (line_number, line) = &lines[current_line_idx];
             if line.is_empty() {
                 current_line_idx += 1;
                 continue;
             }
             match parse_state {
                 ParseState::Start => {
-                    if !self.is_level_line(line) {
+                    if current_line_idx >= board_end {
                         parse_state = ParseState::ReadGameBoard;
                         continue; // no increase of current_line_idx
                     }
@@ -73,21 +557,34 @@ impl LevelLoader {
                         let pos = Point { x: x_pos, y: y_pos };
                         match c {
                             '#' => game_state.set_cell(&pos, GameCell::Unmovable),
-                            'p' | '@' => game_state.set_player_position(&pos),
+                            'p' | '@' => {
+                                game_state.set_player_position(&pos);
+                                players += 1;
+                            }
                             'P' | '+' => {
                                 game_state.set_player_position(&pos);
                                 game_state.set_cell(&pos, GameCell::Sink);
+                                players += 1;
                             }
                             'b' | '$' => {
                                 game_state.set_cell(&pos, GameCell::Box);
+                                boxes_found = true;
                             }
                             'B' | '*' => {
                                 game_state.set_cell(&pos, GameCell::SinkWithBox);
+                                boxes_found = true;
                             }
                             '.' => {
                                 game_state.set_cell(&pos, GameCell::Sink);
                             }
-                            _ => {} // floor
+                            c if ALLOWED_BOARD_CHARS.contains(c) => {} // floor
+                            c => {
+                                return Err(LevelParseError {
+                                    line: *line_number,
+                                    column: x_pos as usize + 1,
+                                    kind: LevelParseErrorKind::UnknownCharacter(c),
+                                });
+                            }
                         }
                         x_pos += 1;
                     }
@@ -98,15 +595,102 @@ impl LevelLoader {
                     let title_prefix = "Title: ";
                     let author_prefix = "Author: ";
 
-                    if line.starts_with(title_prefix) {
-                        title = &line[title_prefix.len()..];
-                    } else if line.starts_with(author_prefix) {
-                        author = &line[author_prefix.len()..];
+                    if let Some(rest) = line.strip_prefix(title_prefix) {
+                        title = rest;
+                    } else if let Some(rest) = line.strip_prefix(author_prefix) {
+                        author = rest;
+                    }
+                    if let Some((key, _)) = line.split_once(':') {
+                        info_keys.push((*line_number, key.trim().to_owned()));
                     }
                 }
             }
             current_line_idx += 1;
         }
-        Game::new(game_state, String::from(title), String::from(author))
+        if players == 0 {
+            return Err(LevelParseError::whole_level(
+                LevelParseErrorKind::MissingPlayer,
+            ));
+        }
+        if !boxes_found {
+            return Err(LevelParseError::whole_level(
+                LevelParseErrorKind::MissingBoxes,
+            ));
+        }
+        Ok(ParsedLevel {
+            game: Game::new(game_state, String::from(title), String::from(author)),
+            players,
+            info_keys,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_after_board_is_a_comment() {
+        let game = LevelLoader::parse_str(
+            "#####\n#@$.#\n#####\nA short one to start with\nTitle: Comment\n",
+        )
+        .unwrap();
+        assert_eq!(game.title(), "Comment");
+        assert_eq!(game.state().height(), 3);
+    }
+
+    #[test]
+    fn comment_starting_with_a_digit_ends_the_board() {
+        let game = LevelLoader::parse_str("#####\n#@$.#\n#####\n3 boxes to go\n").unwrap();
+        assert_eq!(game.state().height(), 3);
+    }
+
+    #[test]
+    fn typo_in_board_row_is_reported() {
+        let Err(error) = LevelLoader::parse_str("#####\n#@$x#\n#####\n") else {
+            panic!("the typo went unnoticed");
+        };
+        assert_eq!(error.kind, LevelParseErrorKind::UnknownCharacter('x'));
+        assert_eq!((error.line, error.column), (2, 4));
+    }
+
+    #[test]
+    fn large_rle_board_is_parsed() {
+        let Err(error) = LevelLoader::parse_str("256(256#|)") else {
+            panic!("a board of walls only has no player");
+        };
+        assert_eq!(error.kind, LevelParseErrorKind::MissingPlayer);
+    }
+
+    #[test]
+    fn too_wide_board_is_reported() {
+        let Err(error) = LevelLoader::parse_str("#@$.7(10000#)") else {
+            panic!("the board was truncated");
+        };
+        assert_eq!(error.kind, LevelParseErrorKind::BoardTooLarge(70004, 1));
+    }
+
+    const SLC_LEVEL: &str =
+        "<LevelCollection><Level><L>#####</L><L>#@$.#</L><L>#####</L></Level></LevelCollection>";
+
+    #[test]
+    fn slc_character_references_and_cdata_are_decoded() {
+        let contents = format!(
+            "<SokobanLevels><Title>Caf&#233; &#x2615; <![CDATA[<Tom & Jerry>]]></Title>{}</SokobanLevels>",
+            SLC_LEVEL.replace("<Level>", "<Level Id=\"N&#xBA; 1\">")
+        );
+        let collection = LevelLoader::parse_slc_str(&contents).unwrap();
+        assert_eq!(collection.title, "Café ☕ <Tom & Jerry>");
+        assert_eq!(collection.levels[0].title(), "Nº 1");
+    }
+
+    #[test]
+    fn slc_unquoted_attribute_is_an_error() {
+        let contents = SLC_LEVEL.replace("<Level>", "<Level Id=é1>");
+        let Err(error) = LevelLoader::parse_slc_str(&contents) else {
+            panic!("the unquoted value went unnoticed");
+        };
+        assert!(matches!(error.kind, LevelParseErrorKind::MalformedXml(_)));
+        assert_eq!((error.line, error.column), (1, 18));
     }
 }
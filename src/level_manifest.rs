@@ -0,0 +1,174 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Reader for levels/index.toml, the list of levels offered in the level chooser.
+// Only the part of TOML the manifest needs is supported:
+//
+//   [[level]]
+//   file = "level_0.lvl"
+//   title = "Tutorial"   # optional, the label in the level chooser
+//
+//   [[collection]]
+//   file = "kanjiban_pack.txt"
+//
+// Entries are offered in the order of the manifest; files are relative to it.
+
+use std::fmt;
+
+pub const MANIFEST_PATH: &str = "levels/index.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestEntry {
+    Level { file: String, title: Option<String> },
+    Collection { file: String },
+}
+
+// Line is 1-based; 0 if the error concerns the whole manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if self.line > 0 {
+            write!(f, " (line {})", self.line)?;
+        }
+        Ok(())
+    }
+}
+
+// The path of a file listed in the manifest.
+pub fn manifest_file_path(file: &str) -> String {
+    match MANIFEST_PATH.rsplit_once('/') {
+        Some((directory, _)) => format!("{directory}/{file}"),
+        None => file.to_owned(),
+    }
+}
+
+// A basic string like "a \"b\"", followed by nothing but an optional comment.
+fn parse_string(value: &str) -> Option<String> {
+    let mut chars = value.strip_prefix('"')?.chars();
+    let mut result = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => match chars.next()? {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                c @ ('"' | '\\') => result.push(c),
+                _ => return None,
+            },
+            c => result.push(c),
+        }
+    }
+    let rest = chars.as_str().trim();
+    (rest.is_empty() || rest.starts_with('#')).then_some(result)
+}
+
+// The entries of the manifest; unknown tables or keys are reported as errors,
+// so typos don't silently drop levels.
+pub fn parse_manifest(contents: &str) -> Result<Vec<ManifestEntry>, ManifestError> {
+    // table name, line of the table header, file and title
+    let mut tables: Vec<(&str, usize, Option<String>, Option<String>)> = Vec::new();
+    for (line_idx, line) in contents.lines().enumerate() {
+        let error = |message: String| ManifestError {
+            line: line_idx + 1,
+            message,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix("[[")
+            .and_then(|rest| rest.split_once("]]"))
+            .map(|(name, _)| name.trim())
+        {
+            if name != "level" && name != "collection" {
+                return Err(error(format!("Unknown table '{name}'")));
+            }
+            tables.push((name, line_idx + 1, None, None));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error("Expected 'key = \"value\"'".to_owned()));
+        };
+        let key = key.trim();
+        let Some(value) = parse_string(value.trim()) else {
+            return Err(error(format!("Invalid string value for '{key}'")));
+        };
+        let Some((name, _, file, title)) = tables.last_mut() else {
+            return Err(error(format!(
+                "'{key}' outside of a [[level]] or [[collection]]"
+            )));
+        };
+        match key {
+            "file" => *file = Some(value),
+            "title" if *name == "level" => *title = Some(value),
+            _ => return Err(error(format!("Unknown key '{key}' in [[{name}]]"))),
+        }
+    }
+    let mut entries = Vec::new();
+    for (name, line, file, title) in tables {
+        let Some(file) = file else {
+            return Err(ManifestError {
+                line,
+                message: format!("[[{name}]] without a file"),
+            });
+        };
+        entries.push(match name {
+            "level" => ManifestEntry::Level { file, title },
+            _ => ManifestEntry::Collection { file },
+        });
+    }
+    if entries.is_empty() {
+        return Err(ManifestError {
+            line: 0,
+            message: "The manifest lists no levels".to_owned(),
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_levels_and_collections() {
+        let manifest = "# levels offered\n[[level]]\nfile = \"level_0.lvl\"\ntitle = \"The \\\"first\\\"\" # comment\n\n[[collection]]\nfile = \"pack.txt\"\n";
+        assert_eq!(
+            parse_manifest(manifest).unwrap(),
+            vec![
+                ManifestEntry::Level {
+                    file: "level_0.lvl".to_owned(),
+                    title: Some("The \"first\"".to_owned()),
+                },
+                ManifestEntry::Collection {
+                    file: "pack.txt".to_owned(),
+                },
+            ]
+        );
+    }
+
+    fn error_line(manifest: &str) -> usize {
+        parse_manifest(manifest).unwrap_err().line
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        assert_eq!(error_line("[[levels]]\nfile = \"a.lvl\"\n"), 1);
+        assert_eq!(error_line("[[level]]\nfile \"a.lvl\"\n"), 2);
+        assert_eq!(error_line("[[level]]\nfile = a.lvl\n"), 2);
+        assert_eq!(error_line("[[level]]\nfile = \"a.lvl\" b\n"), 2);
+        assert_eq!(error_line("file = \"a.lvl\"\n"), 1);
+        assert_eq!(
+            error_line("[[collection]]\nfile = \"a\"\ntitle = \"b\"\n"),
+            3
+        );
+        assert_eq!(error_line("[[level]]\nfile = \"a.lvl\"\n[[level]]\n"), 3);
+        assert_eq!(error_line("# nothing\n"), 0);
+    }
+}
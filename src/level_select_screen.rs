@@ -0,0 +1,148 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Full screen list of all levels with their best scores. Scrolls with the
+// mouse wheel, the arrow keys or by dragging; a click or tap plays a level.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::hud_button::HudButton;
+use crate::level_list::{draw_solved_mark, score_text};
+use crate::macroquad_helpers::get_adjusted_mouse_position;
+use crate::menu::draw_heading;
+
+const LIST_X: f32 = 240.;
+const LIST_WIDTH: f32 = 800.;
+const LIST_TOP: f32 = 110.;
+const LIST_HEIGHT: f32 = 600.;
+const ROW_HEIGHT: f32 = 56.;
+const ROW_STEP: f32 = 60.;
+
+pub struct LevelSelectScreen<'a> {
+    selected: usize,
+    // pixels the list is scrolled down
+    scroll: f32,
+    // last position of a drag scrolling the list
+    drag_y: Option<f32>,
+    back_button: HudButton<'a>,
+}
+
+impl<'a> LevelSelectScreen<'a> {
+    pub fn new(ctx: &AppContext<'a>) -> Self {
+        let mut screen = Self {
+            selected: ctx.current_level,
+            scroll: 0.,
+            drag_y: None,
+            back_button: HudButton::new(
+                ctx.camera,
+                Rect::new(20., 20., 120., 70.),
+                "Back",
+                ctx.fonts,
+            ),
+        };
+        screen.scroll_to(ctx.current_level, ctx.entries.len());
+        screen
+    }
+
+    fn row_rect(&self, index: usize) -> Rect {
+        let y = LIST_TOP + index as f32 * ROW_STEP - self.scroll;
+        Rect::new(LIST_X, y, LIST_WIDTH, ROW_HEIGHT)
+    }
+
+    fn scroll_by(&mut self, delta: f32, count: usize) {
+        let max_scroll = (count as f32 * ROW_STEP - LIST_HEIGHT).max(0.);
+        self.scroll = (self.scroll + delta).clamp(0., max_scroll);
+    }
+
+    // Scroll just far enough to show the given row.
+    fn scroll_to(&mut self, index: usize, count: usize) {
+        let top = index as f32 * ROW_STEP;
+        if top < self.scroll {
+            self.scroll_by(top - self.scroll, count);
+        } else if top + ROW_HEIGHT > self.scroll + LIST_HEIGHT {
+            self.scroll_by(top + ROW_HEIGHT - LIST_HEIGHT - self.scroll, count);
+        }
+    }
+}
+
+impl<'a> Screen<'a> for LevelSelectScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        if is_key_pressed(KeyCode::Escape) || self.back_button.update() {
+            return Transition::Pop(1);
+        }
+        let count = ctx.entries.len();
+        let page = (LIST_HEIGHT / ROW_STEP) as usize;
+        let selected = if is_key_pressed(KeyCode::Down) {
+            self.selected + 1
+        } else if is_key_pressed(KeyCode::Up) {
+            self.selected.saturating_sub(1)
+        } else if is_key_pressed(KeyCode::PageDown) {
+            self.selected + page
+        } else if is_key_pressed(KeyCode::PageUp) {
+            self.selected.saturating_sub(page)
+        } else if is_key_pressed(KeyCode::Enter) {
+            return Transition::PlayLevel(self.selected);
+        } else {
+            self.selected
+        };
+        if selected != self.selected {
+            self.selected = selected.min(count - 1);
+            self.scroll_to(self.selected, count);
+        }
+        let wheel = mouse_wheel().1;
+        if wheel != 0. {
+            self.scroll_by(-wheel.signum() * ROW_STEP, count);
+        }
+        let position = get_adjusted_mouse_position(ctx.camera);
+        let list_rect = Rect::new(LIST_X, LIST_TOP, LIST_WIDTH, LIST_HEIGHT);
+        if is_mouse_button_pressed(MouseButton::Left) && list_rect.contains(position) {
+            self.drag_y = Some(position.y);
+        } else if !is_mouse_button_down(MouseButton::Left) {
+            self.drag_y = None;
+        }
+        if let Some(last_y) = self.drag_y {
+            self.scroll_by(last_y - position.y, count);
+            self.drag_y = Some(position.y);
+        }
+        if let Some(position) = ctx.input_control.get_click(ctx.camera, true)
+            && list_rect.contains(position)
+            && let Some(index) = (0..count).find(|i| self.row_rect(*i).contains(position))
+        {
+            return Transition::PlayLevel(index);
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        clear_background(LIGHTGRAY);
+        let text_params = TextParams {
+            font: Some(ctx.fonts.font()),
+            font_size: 34,
+            color: WHITE,
+            ..Default::default()
+        };
+        for (index, entry) in ctx.entries.iter().enumerate() {
+            let rect = self.row_rect(index);
+            if rect.bottom() < LIST_TOP || rect.top() > LIST_TOP + LIST_HEIGHT {
+                continue;
+            }
+            let color = if index == self.selected {
+                GRAY
+            } else {
+                DARKGRAY
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            let text_y = rect.y + 40.;
+            // leave room for the checkmark
+            draw_text_ex(&entry.label, rect.x + 50., text_y, text_params.clone());
+            if let Some(record) = ctx.save_data.record(&entry.source.key()) {
+                draw_solved_mark(&score_text(record), rect, text_y, ctx.fonts);
+            }
+        }
+        // rows scrolled out of the list are hidden below the heading and the bottom
+        draw_rectangle(0., 0., 1280., LIST_TOP, LIGHTGRAY);
+        draw_rectangle(0., LIST_TOP + LIST_HEIGHT, 1280., 720., LIGHTGRAY);
+        draw_heading("Choose a level", 75., 50, ctx.fonts);
+        self.back_button.draw();
+    }
+}
@@ -0,0 +1,220 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Semantic checks for levels that parse fine but can't be played properly.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::game_logic::{GameCell, GameState, Point};
+use crate::input_control::{DIR_DOWN, DIR_LEFT, DIR_RIGHT, DIR_UP};
+use crate::level_loader::ParsedLevel;
+
+const KNOWN_INFO_KEYS: &[&str] = &["Title", "Author", "Comment"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// Line is 1-based; 0 if the diagnostic concerns the whole level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            line: 0,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "Error: {}", self.message)?,
+            Severity::Warning => write!(f, "Warning: {}", self.message)?,
+        }
+        if self.line > 0 {
+            write!(f, " (line {})", self.line)?;
+        }
+        Ok(())
+    }
+}
+
+// Check a level read from text; an empty list means the level is fine.
+pub fn validate_level(level: &ParsedLevel) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = level
+        .info_keys
+        .iter()
+        .filter(|(_, key)| !KNOWN_INFO_KEYS.contains(&key.as_str()))
+        .map(|(line, key)| Diagnostic {
+            severity: Severity::Warning,
+            line: *line,
+            message: format!("Unknown info key '{key}'"),
+        })
+        .collect();
+    if level.players != 1 {
+        diagnostics.push(Diagnostic::error(format!(
+            "Level has {} players instead of one",
+            level.players
+        )));
+    }
+    diagnostics.extend(validate_state(level.game.state()));
+    diagnostics
+}
+
+// Checks on the board itself: counts of boxes and sinks, walls around the
+// player and reachability of every box.
+pub fn validate_state(state: &GameState) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut boxes = 0;
+    let mut sinks = 0;
+    let mut unsolved_boxes = 0;
+    for y in 0..state.height() {
+        for x in 0..state.width() {
+            match state.get_cell(&Point { x, y }) {
+                GameCell::Box => {
+                    boxes += 1;
+                    unsolved_boxes += 1;
+                }
+                GameCell::Sink => sinks += 1,
+                GameCell::SinkWithBox => {
+                    boxes += 1;
+                    sinks += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    if boxes != sinks {
+        diagnostics.push(Diagnostic::error(format!(
+            "Level has {boxes} boxes but {sinks} sinks"
+        )));
+    }
+    if unsolved_boxes == 0 {
+        diagnostics.push(Diagnostic::error(
+            "All boxes are on sinks already".to_owned(),
+        ));
+    }
+    // area of the player, walking through boxes
+    let width = state.width();
+    let mut visited = vec![false; (width * state.height()) as usize];
+    let start = state.get_player_position();
+    let mut enclosed = true;
+    let mut queue = VecDeque::from([start]);
+    visited[(start.y * width + start.x) as usize] = true;
+    while let Some(current) = queue.pop_front() {
+        if current.x == 0
+            || current.y == 0
+            || current.x == width - 1
+            || current.y == state.height() - 1
+        {
+            enclosed = false;
+        }
+        for direction in [DIR_LEFT, DIR_UP, DIR_RIGHT, DIR_DOWN] {
+            let next = current + direction;
+            if state.get_cell(&next) != GameCell::Unmovable
+                && !visited[(next.y * width + next.x) as usize]
+            {
+                visited[(next.y * width + next.x) as usize] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    if !enclosed {
+        diagnostics.push(Diagnostic::error(
+            "The player's area is not enclosed by walls".to_owned(),
+        ));
+    }
+    for y in 0..state.height() {
+        for x in 0..width {
+            let pos = Point { x, y };
+            if state.is_occupied_by_box(&pos) && !visited[(y * width + x) as usize] {
+                diagnostics.push(Diagnostic::error(format!(
+                    "Box in column {}, row {} can't be reached",
+                    x + 1,
+                    y + 1
+                )));
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_loader::LevelLoader;
+
+    fn diagnostics(level: &str) -> Vec<Diagnostic> {
+        validate_level(&LevelLoader::parse_level(level).unwrap())
+    }
+
+    fn messages(level: &str) -> Vec<String> {
+        diagnostics(level).iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn good_level_has_no_diagnostics() {
+        assert!(diagnostics("#####\n#@$.#\n#####\nTitle: Good\nAuthor: JoAn\n").is_empty());
+    }
+
+    #[test]
+    fn unknown_info_key_is_a_warning() {
+        let diagnostics = diagnostics("#####\n#@$.#\n#####\nTitel: Typo\n");
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                severity: Severity::Warning,
+                line: 4,
+                message: "Unknown info key 'Titel'".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn player_count_is_checked() {
+        assert_eq!(
+            messages("######\n#@$.@#\n######\n"),
+            ["Error: Level has 2 players instead of one"]
+        );
+    }
+
+    #[test]
+    fn box_and_sink_counts_are_checked() {
+        assert_eq!(
+            messages("######\n#@$$.#\n######\n"),
+            ["Error: Level has 2 boxes but 1 sinks"]
+        );
+        assert_eq!(
+            messages("#####\n#@*.#\n#####\n"),
+            [
+                "Error: Level has 1 boxes but 2 sinks",
+                "Error: All boxes are on sinks already"
+            ]
+        );
+    }
+
+    #[test]
+    fn open_area_is_reported() {
+        assert_eq!(
+            messages("#####\n#@$. \n#####\n"),
+            ["Error: The player's area is not enclosed by walls"]
+        );
+    }
+
+    #[test]
+    fn unreachable_box_is_reported() {
+        assert_eq!(
+            messages("#######\n#@$.#$#\n#  .###\n#######\n"),
+            ["Error: Box in column 6, row 2 can't be reached"]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Writes levels as standard XSB text, which the level loader reads back.
+use std::io::{self, Write};
+
+use crate::game_logic::{Game, GameCell, GameState, Point};
+
+// The XSB character of a cell, including the player standing on it. XSB has
+// no character for the player on a box or wall, so that is an error.
+pub fn cell_char(state: &GameState, pos: &Point) -> io::Result<char> {
+    let on_player = *pos == state.get_player_position();
+    Ok(match state.get_cell(pos) {
+        GameCell::Unmovable | GameCell::Box | GameCell::SinkWithBox if on_player => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the player stands on a box or wall in column {}, row {}",
+                    pos.x + 1,
+                    pos.y + 1
+                ),
+            ));
+        }
+        GameCell::Unmovable => '#',
+        GameCell::Box => '$',
+        GameCell::SinkWithBox => '*',
+        GameCell::Sink if on_player => '+',
+        GameCell::Sink => '.',
+        _ if on_player => '@',
+        _ => ' ',
+    })
+}
+
+// Write the board, one line per row. Rows keep their trailing floor, so the
+// board has the same size when read back.
+pub fn write_state<W: Write>(state: &GameState, writer: &mut W) -> io::Result<()> {
+    for y in 0..state.height() {
+        let row: String = (0..state.width())
+            .map(|x| cell_char(state, &Point { x, y }))
+            .collect::<io::Result<_>>()?;
+        writeln!(writer, "{row}")?;
+    }
+    Ok(())
+}
+
+// Write the current board of the game, followed by its title and author.
+pub fn write_level<W: Write>(game: &Game, writer: &mut W) -> io::Result<()> {
+    write_state(game.state(), writer)?;
+    if !game.title().is_empty() {
+        writeln!(writer, "Title: {}", game.title())?;
+    }
+    if !game.author().is_empty() {
+        writeln!(writer, "Author: {}", game.author())?;
+    }
+    Ok(())
+}
+
+// The level as XSB text; fails only if the board can't be written as XSB.
+pub fn level_to_string(game: &Game) -> io::Result<String> {
+    let mut buffer = Vec::new();
+    write_level(game, &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_loader::LevelLoader;
+
+    const LEVEL: &str = "######\n#+$ *#\n# $ .#\n######\nTitle: Round trip\nAuthor: JoAn\n";
+
+    #[test]
+    fn written_level_reads_back() {
+        let game = LevelLoader::parse_str(LEVEL).unwrap();
+        let written = level_to_string(&game).unwrap();
+        assert_eq!(written, LEVEL);
+        let read = LevelLoader::parse_str(&written).unwrap();
+        assert!(read.state() == game.state());
+        assert_eq!((read.title(), read.author()), (game.title(), game.author()));
+    }
+
+    #[test]
+    fn player_on_box_or_wall_is_an_error() {
+        let mut state = LevelLoader::parse_str(LEVEL).unwrap().state().clone();
+        // a box, a box on a sink and a wall
+        for (x, y) in [(2, 1), (4, 1), (0, 0)] {
+            state.set_player_position(&Point { x, y });
+            let game = Game::new(state.clone(), String::new(), String::new());
+            let error = level_to_string(&game).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
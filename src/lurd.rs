@@ -0,0 +1,73 @@
+// kanjiban
+// (C) 2025 by JoAn
+// LURD move notation: lowercase letters for walks, uppercase letters for pushes.
+
+use std::fmt;
+
+use crate::game_logic::{Move, Point};
+use crate::input_control::{DIR_DOWN, DIR_LEFT, DIR_RIGHT, DIR_UP};
+use crate::rle::RleError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LurdErrorKind {
+    UnknownCharacter(char),
+    IllegalMove,
+    // the step did or didn't push, unlike the case of the letter says; holds
+    // the letter that fits
+    WrongCase(char),
+    // the run-length encoding couldn't be expanded; it tells the position
+    Rle(RleError),
+}
+
+// Position (in characters of the LURD string) where replaying failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LurdError {
+    pub index: usize,
+    pub kind: LurdErrorKind,
+}
+
+impl fmt::Display for LurdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LurdErrorKind::UnknownCharacter(c) => {
+                write!(f, "Unknown move '{}' at position {}", c, self.index)
+            }
+            LurdErrorKind::IllegalMove => write!(f, "Illegal move at position {}", self.index),
+            LurdErrorKind::Rle(error) => write!(f, "{error}"),
+            LurdErrorKind::WrongCase(c) => {
+                write!(f, "The move at position {} should be '{}'", self.index, c)
+            }
+        }
+    }
+}
+
+pub fn move_to_char(performed: &Move) -> char {
+    let c = match performed.direction {
+        DIR_LEFT => 'l',
+        DIR_UP => 'u',
+        DIR_RIGHT => 'r',
+        DIR_DOWN => 'd',
+        _ => unreachable!("a move is a single step"),
+    };
+    if performed.pushed {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+// The direction of a LURD character, whatever its case; whether the step
+// pushes is for the caller to check.
+pub fn char_to_direction(c: char) -> Option<Point> {
+    match c.to_ascii_lowercase() {
+        'l' => Some(DIR_LEFT),
+        'u' => Some(DIR_UP),
+        'r' => Some(DIR_RIGHT),
+        'd' => Some(DIR_DOWN),
+        _ => None,
+    }
+}
+
+pub fn to_lurd(moves: &[Move]) -> String {
+    moves.iter().map(move_to_char).collect()
+}
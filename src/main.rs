@@ -1,16 +1,39 @@
 use macroquad::prelude::*;
+mod animation;
+mod app_state;
+mod deadlock;
+mod editor_screen;
+#[cfg(feature = "embedded-levels")]
+mod embedded_levels;
 mod game_board;
 mod game_logic;
+mod hud_button;
 mod input_control;
 mod level_chooser;
+mod level_editor;
+mod level_list;
 mod level_loader;
+mod level_manifest;
+mod level_select_screen;
+mod level_validator;
+mod level_writer;
+mod lurd;
 mod macroquad_helpers;
+mod menu;
+mod pause_screen;
+mod play_screen;
+mod rle;
+mod save_data;
+mod settings_screen;
+mod solver;
+mod title_screen;
+mod win_screen;
+use crate::app_state::{AppContext, ScreenStack};
 use crate::game_board::*;
-use crate::game_logic::{Game, Point};
-use crate::input_control::{DIR_NO_MOVE, InputControl};
-use crate::level_chooser::LevelChooser;
-use crate::level_loader::LevelLoader;
+use crate::level_list::level_entries;
 use crate::macroquad_helpers::FontProvider;
+use crate::play_screen::draw_load_error;
+use crate::title_screen::TitleScreen;
 
 fn window_conf() -> Conf {
     Conf {
@@ -22,52 +45,6 @@ fn window_conf() -> Conf {
     }
 }
 
-static LEVELS: &[&str] = &["0", "1", "2", "3", "4", "5"];
-
-async fn load_level(level_prefix: &str) -> Game {
-    let ll = LevelLoader::new(&format!("{}{}{}", "levels/level_", level_prefix, ".lvl"));
-    ll.parse_level().await
-}
-
-fn draw_status_bar(game: &Game, fonts: &FontProvider) {
-    let steps = game.steps();
-    let start_x = 35.;
-    let start_y = 65.;
-    let mut text_height = start_y;
-    let gray_text_params = TextParams {
-        font: Some(fonts.font()),
-        font_size: 50,
-        color: DARKGRAY,
-        ..Default::default()
-    };
-    let blue_text_params = TextParams {
-        font: Some(fonts.font()),
-        font_size: 30,
-        color: BLUE,
-        ..Default::default()
-    };
-    draw_text_ex(
-        format!("Steps: {steps}").as_str(),
-        start_x,
-        text_height,
-        gray_text_params,
-    );
-    text_height = 30.;
-    draw_text_ex(
-        game.title(),
-        start_x + 250.0,
-        text_height,
-        blue_text_params.clone(),
-    );
-    text_height += 30.;
-    draw_text_ex(
-        game.author(),
-        start_x + 250.0,
-        text_height,
-        blue_text_params.clone(),
-    );
-}
-
 #[macroquad::main(window_conf)]
 async fn main() {
     let fonts = FontProvider::new().await;
@@ -81,41 +58,17 @@ async fn main() {
     };
     set_camera(&camera);
     let game_board = GameBoard::new(2., 110., 1270., 600.).await;
-    let mut level_chooser = LevelChooser::new(&camera, 920.0, 2., 350.0, LEVELS, &fonts);
-    let mut input_control = InputControl::new();
-    let speed: f64 = 0.25;
-    let mut last_update = get_time();
-    let mut game_over: bool = false; // TODO: move to state
-    let mut game: Game = load_level("0").await;
-    let mut desired_direction: Point = DIR_NO_MOVE;
-
-    loop {
-        if let Some(selected) = level_chooser.update() {
-            game = load_level(LEVELS[selected]).await;
-            game_over = false;
-        }
-        let current_direction = input_control.get_direction();
-        if current_direction != DIR_NO_MOVE {
-            desired_direction = current_direction;
-        }
-        if get_time() - last_update > speed {
-            last_update = get_time();
-            // player likes to move on this tile:
-            if game.try_perform_move(&desired_direction) {
-                // check whether move is possible
-                game_over = game.is_game_won();
-            }
-            desired_direction = DIR_NO_MOVE;
-        }
-
-        if !game_over {
-            game_board.draw_board(game.state());
-        } else {
-            game_board.draw_win(game.state());
-        }
-        draw_status_bar(&game, &fonts);
-        level_chooser.draw();
-
+    let entries = match level_entries().await {
+        Ok(entries) => entries,
+        Err(error) => loop {
+            draw_load_error("The level list could not be loaded:", &error, &fonts);
+            next_frame().await;
+        },
+    };
+    let mut ctx = AppContext::new(&camera, &fonts, entries, game_board);
+    let mut screens = ScreenStack::new(Box::new(TitleScreen::new(&ctx)));
+    while screens.update(&mut ctx).await {
+        screens.draw(&ctx);
         next_frame().await;
     }
 }
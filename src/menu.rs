@@ -0,0 +1,117 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Vertical list of menu items, chosen with the arrow keys and Enter, or by
+// clicking or tapping them.
+
+use crate::macroquad_helpers::{FontProvider, get_adjusted_mouse_position};
+use macroquad::prelude::*;
+
+const ITEM_HEIGHT: f32 = 70.;
+const ITEM_GAP: f32 = 12.;
+
+pub struct Menu<'a> {
+    items: Vec<String>,
+    selected: usize,
+    // top left corner of the first item
+    x: f32,
+    y: f32,
+    width: f32,
+    camera: &'a Camera2D,
+    fonts: &'a FontProvider,
+}
+
+impl<'a> Menu<'a> {
+    // A menu horizontally centered in the virtual screen, starting at y.
+    pub fn new(camera: &'a Camera2D, y: f32, items: Vec<String>, fonts: &'a FontProvider) -> Self {
+        let width = 420.;
+        Self {
+            items,
+            selected: 0,
+            x: (1280. - width) / 2.,
+            y,
+            width,
+            camera,
+            fonts,
+        }
+    }
+
+    pub fn set_item(&mut self, index: usize, label: String) {
+        self.items[index] = label;
+    }
+
+    fn item_rect(&self, index: usize) -> Rect {
+        let y = self.y + index as f32 * (ITEM_HEIGHT + ITEM_GAP);
+        Rect::new(self.x, y, self.width, ITEM_HEIGHT)
+    }
+
+    fn item_at(&self, position: Vec2) -> Option<usize> {
+        (0..self.items.len()).find(|i| self.item_rect(*i).contains(position))
+    }
+
+    // Returns the index of the item chosen in this frame, if any.
+    pub fn update(&mut self) -> Option<usize> {
+        let count = self.items.len();
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        } else if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            return Some(self.selected);
+        }
+        let position = get_adjusted_mouse_position(self.camera);
+        if let Some(index) = self.item_at(position) {
+            // follow the mouse only while it moves, so it doesn't fight the keys
+            if mouse_delta_position() != Vec2::ZERO {
+                self.selected = index;
+            }
+            if is_mouse_button_pressed(MouseButton::Left) {
+                self.selected = index;
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        for (index, item) in self.items.iter().enumerate() {
+            let rect = self.item_rect(index);
+            let color = if index == self.selected {
+                GRAY
+            } else {
+                DARKGRAY
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            if index == self.selected {
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 4., GOLD);
+            }
+            let text_size = measure_text(item, Some(self.fonts.font()), 34, 1.0);
+            draw_text_ex(
+                item,
+                rect.x + (rect.w - text_size.width) / 2.,
+                rect.y + (rect.h + text_size.offset_y) / 2.,
+                TextParams {
+                    font: Some(self.fonts.font()),
+                    font_size: 34,
+                    color: WHITE,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+// A heading centered in the virtual screen, with its baseline at y.
+pub fn draw_heading(text: &str, y: f32, font_size: u16, fonts: &FontProvider) {
+    let text_size = measure_text(text, Some(fonts.font()), font_size, 1.0);
+    draw_text_ex(
+        text,
+        (1280. - text_size.width) / 2.,
+        y,
+        TextParams {
+            font: Some(fonts.font()),
+            font_size,
+            color: DARKGRAY,
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,109 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Pause menu shown on top of the level being played.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::editor_screen::EditorScreen;
+use crate::game_logic::GameState;
+use crate::input_control::Command;
+use crate::level_select_screen::LevelSelectScreen;
+use crate::menu::{Menu, draw_heading};
+use crate::settings_screen::SettingsScreen;
+
+#[derive(Clone, Copy)]
+enum PauseAction {
+    Continue,
+    EditLevel,
+    BackToEditor,
+    ChooseLevel,
+    Reverse,
+    Settings,
+    TitleMenu,
+}
+
+const ACTIONS: &[(&str, PauseAction)] = &[
+    ("Continue", PauseAction::Continue),
+    ("Edit level", PauseAction::EditLevel),
+    ("Choose level", PauseAction::ChooseLevel),
+    ("Play backwards", PauseAction::Reverse),
+    ("Settings", PauseAction::Settings),
+    ("Title menu", PauseAction::TitleMenu),
+];
+
+const TEST_PLAY_ACTIONS: &[(&str, PauseAction)] = &[
+    ("Continue", PauseAction::Continue),
+    ("Back to editor", PauseAction::BackToEditor),
+    ("Play backwards", PauseAction::Reverse),
+    ("Settings", PauseAction::Settings),
+];
+
+pub struct PauseScreen<'a> {
+    actions: &'static [(&'static str, PauseAction)],
+    menu: Menu<'a>,
+    // the start of the level paused in, to edit
+    state: GameState,
+}
+
+impl<'a> PauseScreen<'a> {
+    // reverse tells whether the level is played backwards right now
+    pub fn new(ctx: &AppContext<'a>, state: GameState, test_play: bool, reverse: bool) -> Self {
+        let actions = if test_play {
+            TEST_PLAY_ACTIONS
+        } else {
+            ACTIONS
+        };
+        let items = actions
+            .iter()
+            .map(|(label, action)| match action {
+                PauseAction::Reverse if reverse => "Play forwards".to_owned(),
+                _ => label.to_string(),
+            })
+            .collect();
+        Self {
+            actions,
+            menu: Menu::new(ctx.camera, 190., items, ctx.fonts),
+            state,
+        }
+    }
+}
+
+impl<'a> Screen<'a> for PauseScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop(1);
+        }
+        let Some(index) = self.menu.update() else {
+            return Transition::Stay;
+        };
+        match self.actions[index].1 {
+            PauseAction::Continue => Transition::Pop(1),
+            PauseAction::EditLevel => {
+                Transition::Replace(1, Box::new(EditorScreen::new(ctx, self.state.clone())))
+            }
+            // pause menu and test play
+            PauseAction::BackToEditor => Transition::Pop(2),
+            PauseAction::ChooseLevel => {
+                Transition::Replace(1, Box::new(LevelSelectScreen::new(ctx)))
+            }
+            PauseAction::Reverse => {
+                ctx.pending_command = Some(Command::Reverse);
+                Transition::Pop(1)
+            }
+            PauseAction::Settings => Transition::Push(Box::new(SettingsScreen::new(ctx))),
+            PauseAction::TitleMenu => Transition::ToTitle,
+        }
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        draw_rectangle(0., 0., 1280., 720., Color::new(0., 0., 0., 0.5));
+        draw_rectangle(390., 70., 500., 620., LIGHTGRAY);
+        draw_heading("Paused", 150., 70, ctx.fonts);
+        self.menu.draw();
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
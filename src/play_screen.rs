@@ -0,0 +1,649 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Playing a level: the board, the status bar and the head-up display.
+
+use macroquad::miniquad::window::{clipboard_get, clipboard_set};
+use macroquad::prelude::*;
+
+use crate::animation::BoardAnimation;
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::editor_screen::EditorScreen;
+use crate::game_board::GameBoard;
+use crate::game_logic::{Game, GameState, Point};
+use crate::hud_button::HudButton;
+use crate::input_control::{Command, DIR_NO_MOVE, ViewGesture};
+use crate::level_list::{load_level_or_empty, score_text};
+use crate::level_loader::LevelParseError;
+use crate::level_writer::level_to_string;
+use crate::lurd::{char_to_direction, to_lurd};
+use crate::macroquad_helpers::FontProvider;
+use crate::pause_screen::PauseScreen;
+use crate::rle::state_to_rle;
+use crate::save_data::LevelRecord;
+use crate::solver::{FRAME_SLICE, Push, Solver, SolverLimits, SolverResult, first_push};
+use crate::win_screen::{Solution, WinScreen};
+
+const RESUME_NOTICE: &str = "Continue where you left off? Press Enter or Resume";
+const AUTOSAVE_INTERVAL: f64 = 10.0;
+const FORWARDS_ONLY_NOTICE: &str = "Hints and the solver work when playing forwards";
+
+pub fn draw_load_error(heading: &str, error: &dyn std::fmt::Display, fonts: &FontProvider) {
+    clear_background(LIGHTGRAY);
+    let start_x = 35.;
+    draw_text_ex(
+        heading,
+        start_x,
+        300.,
+        TextParams {
+            font: Some(fonts.font()),
+            font_size: 50,
+            color: DARKGRAY,
+            ..Default::default()
+        },
+    );
+    draw_text_ex(
+        error.to_string(),
+        start_x,
+        370.,
+        TextParams {
+            font: Some(fonts.font()),
+            font_size: 34,
+            color: MAROON,
+            ..Default::default()
+        },
+    );
+}
+
+fn draw_status_bar(game: &Game, notice: Option<&str>, fonts: &FontProvider) {
+    let moves = game.moves();
+    let pushes = game.pushes();
+    let start_x = 35.;
+    let start_y = 38.;
+    let mut text_height = start_y;
+    let gray_text_params = TextParams {
+        font: Some(fonts.font()),
+        font_size: 34,
+        color: DARKGRAY,
+        ..Default::default()
+    };
+    let blue_text_params = TextParams {
+        font: Some(fonts.font()),
+        font_size: 30,
+        color: BLUE,
+        ..Default::default()
+    };
+    draw_text_ex(
+        format!("Moves: {moves}").as_str(),
+        start_x,
+        text_height,
+        gray_text_params.clone(),
+    );
+    text_height += 40.;
+    let pushes_label = if game.is_reverse() { "Pulls" } else { "Pushes" };
+    draw_text_ex(
+        format!("{pushes_label}: {pushes}").as_str(),
+        start_x,
+        text_height,
+        gray_text_params,
+    );
+    text_height = 30.;
+    draw_text_ex(
+        game.title(),
+        start_x + 250.0,
+        text_height,
+        blue_text_params.clone(),
+    );
+    text_height += 30.;
+    draw_text_ex(
+        game.author(),
+        start_x + 250.0,
+        text_height,
+        blue_text_params.clone(),
+    );
+    if let Some(notice) = notice {
+        text_height += 35.;
+        draw_text_ex(
+            notice,
+            start_x + 250.0,
+            text_height,
+            TextParams {
+                font: Some(fonts.font()),
+                font_size: 24,
+                color: MAROON,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+pub fn apply_view_gesture(game_board: &mut GameBoard, state: &GameState, gesture: &ViewGesture) {
+    if gesture.reset {
+        game_board.reset_view();
+    }
+    let zoom_center = gesture.center.unwrap_or(game_board.center());
+    if gesture.zoom != 1. && game_board.contains(zoom_center) {
+        game_board.zoom_at(state, gesture.zoom, zoom_center);
+    }
+    game_board.pan_by(state, gesture.pan);
+}
+
+pub struct PlayScreen<'a> {
+    // index in the level list; None while test playing a level of the editor
+    level: Option<usize>,
+    game: Game,
+    // the game not shown: the forward one while playing backwards, or the
+    // backward one kept for switching back
+    other_game: Option<Game>,
+    load_error: Option<LevelParseError>,
+    game_over: bool,
+    // game_over of the previous frame, to notice when a level gets solved
+    was_game_over: bool,
+    desired_direction: Point,
+    // saved moves of the level the player may continue with
+    resume: Option<String>,
+    notice: Option<String>,
+    // hint together with the position it was computed for
+    hint: Option<(GameState, Push)>,
+    // running search for a solution or hint, with the position it started from
+    solving: Option<(Solver, Command, GameState)>,
+    // box chosen to be pushed to the next clicked cell
+    selected_box: Option<Point>,
+    animation: BoardAnimation,
+    last_update: f64,
+    last_autosave: f64,
+    // seconds spent playing; time in menus on top doesn't count
+    play_time: f64,
+    // the moves are a solution played back
+    replaying: bool,
+    // best score before the level got solved
+    previous_best: Option<LevelRecord>,
+    // moves made when the solver finished the level; a solution with the
+    // solver's moves isn't recorded as the player's
+    assisted_from: Option<u64>,
+    undo_button: HudButton<'a>,
+    redo_button: HudButton<'a>,
+    reset_button: HudButton<'a>,
+    hint_button: HudButton<'a>,
+    resume_button: HudButton<'a>,
+    menu_button: HudButton<'a>,
+}
+
+impl<'a> PlayScreen<'a> {
+    pub fn new(ctx: &AppContext<'a>, game: Game, level: Option<usize>) -> Self {
+        let (camera, fonts) = (ctx.camera, ctx.fonts);
+        Self {
+            level,
+            game,
+            other_game: None,
+            load_error: None,
+            game_over: false,
+            was_game_over: false,
+            desired_direction: DIR_NO_MOVE,
+            resume: None,
+            notice: None,
+            hint: None,
+            solving: None,
+            selected_box: None,
+            animation: BoardAnimation::new(ctx.settings.step_duration()),
+            last_update: get_time(),
+            last_autosave: get_time(),
+            play_time: 0.,
+            replaying: false,
+            previous_best: None,
+            assisted_from: None,
+            undo_button: HudButton::new(camera, Rect::new(475., 2., 80., 80.), "Undo", fonts),
+            redo_button: HudButton::new(camera, Rect::new(560., 2., 80., 80.), "Redo", fonts),
+            reset_button: HudButton::new(camera, Rect::new(645., 2., 80., 80.), "Reset", fonts),
+            hint_button: HudButton::new(camera, Rect::new(730., 2., 80., 80.), "Hint", fonts),
+            resume_button: HudButton::new(camera, Rect::new(815., 2., 100., 80.), "Resume", fonts),
+            menu_button: HudButton::new(camera, Rect::new(815., 2., 100., 80.), "Menu", fonts),
+        }
+    }
+
+    // Play a level of the level list, offering to continue with saved moves.
+    pub async fn load(ctx: &AppContext<'a>, level: usize) -> Self {
+        let source = &ctx.entries[level].source;
+        let (game, load_error, diagnostics) = load_level_or_empty(source).await;
+        let mut screen = Self::new(ctx, game, Some(level));
+        screen.load_error = load_error;
+        screen.resume = ctx.save_data.progress(&source.key()).map(String::from);
+        screen.notice = diagnostics
+            .first()
+            .map(|d| d.to_string())
+            .or(screen.resume.as_ref().map(|_| RESUME_NOTICE.to_owned()));
+        screen
+    }
+
+    // Try out a level of the editor; nothing about it is saved.
+    pub fn test_play(ctx: &AppContext<'a>, state: GameState) -> Self {
+        let game = Game::new(state, "Test play".to_owned(), String::new());
+        Self::new(ctx, game, None)
+    }
+
+    // Play back a solution step by step; the player may take over at any time.
+    pub fn replay(ctx: &AppContext<'a>, solution: &Solution, directions: Vec<Point>) -> Self {
+        let mut screen = Self::new(ctx, solution.new_game(), solution.level);
+        screen.game.plan_moves(directions);
+        screen.play_time = solution.time;
+        screen.replaying = true;
+        screen.assisted_from = solution.assisted_from;
+        screen
+    }
+
+    // The game played forwards; its moves are the ones saved.
+    fn forward_game(&self) -> &Game {
+        match &self.other_game {
+            Some(other) if self.game.is_reverse() => other,
+            _ => &self.game,
+        }
+    }
+
+    // Play the given game forwards; moves made backwards are dropped.
+    fn set_forward_game(&mut self, game: Game) {
+        self.game = game;
+        self.other_game = None;
+    }
+
+    // A new game of this level, played forwards from the start.
+    fn new_game(&self) -> Game {
+        let forward = self.forward_game();
+        Game::new(
+            forward.initial_state().clone(),
+            forward.title().to_owned(),
+            forward.author().to_owned(),
+        )
+    }
+
+    // A new game of this level with the given moves replayed.
+    fn replayed(&self, lurd: &str) -> Result<Game, String> {
+        let mut game = self.new_game();
+        game.replay_lurd(lurd).map_err(|e| e.to_string())?;
+        Ok(game)
+    }
+
+    fn run_command(&mut self, ctx: &mut AppContext<'a>, command: Command) -> Transition<'a> {
+        match command {
+            Command::Undo => {
+                self.game.undo();
+            }
+            Command::Redo => {
+                self.game.redo();
+            }
+            Command::Reset => {
+                if self.game.reset() {
+                    self.selected_box = None;
+                    self.notice = Some("Back at the start, Undo brings the moves back".to_owned());
+                }
+            }
+            Command::CopyMoves => {
+                clipboard_set(&self.forward_game().lurd());
+                self.notice = Some("Moves copied to clipboard".to_owned());
+            }
+            Command::CopyLevel => match level_to_string(self.forward_game()) {
+                Ok(level) => {
+                    clipboard_set(&level);
+                    self.notice = Some("Level copied to clipboard".to_owned());
+                }
+                Err(error) => self.notice = Some(format!("Can't copy level: {error}")),
+            },
+            Command::CopyLevelRle => match state_to_rle(self.forward_game().state()) {
+                Ok(level) => {
+                    clipboard_set(&level);
+                    self.notice = Some("Level copied to clipboard as RLE".to_owned());
+                }
+                Err(error) => self.notice = Some(format!("Can't copy level: {error}")),
+            },
+            Command::PasteMoves => {
+                // replay from the start of the level
+                if let Some(lurd) = clipboard_get() {
+                    match self.replayed(&lurd) {
+                        Ok(game) => {
+                            self.set_forward_game(game);
+                            self.notice = None;
+                        }
+                        Err(error) => self.notice = Some(error),
+                    }
+                }
+            }
+            Command::Solve | Command::Hint if self.game.is_reverse() => {
+                self.notice = Some(FORWARDS_ONLY_NOTICE.to_owned());
+            }
+            Command::Solve | Command::Hint => {
+                let solver = Solver::new(self.game.state(), SolverLimits::default());
+                self.solving = Some((solver, command, self.game.state().clone()));
+                self.notice = Some(if command == Command::Solve {
+                    "Solving...".to_owned()
+                } else {
+                    "Looking for a hint...".to_owned()
+                });
+            }
+            Command::Edit => {
+                // a test play goes back to its editor
+                if self.level.is_none() {
+                    return Transition::Pop(1);
+                }
+                // the level as it starts, not the position played to
+                let editor = EditorScreen::new(ctx, self.forward_game().initial_state().clone());
+                return Transition::Push(Box::new(editor));
+            }
+            Command::Pause => {
+                let pause = PauseScreen::new(
+                    ctx,
+                    self.forward_game().initial_state().clone(),
+                    self.level.is_none(),
+                    self.game.is_reverse(),
+                );
+                return Transition::Push(Box::new(pause));
+            }
+            Command::Reverse => {
+                // either game keeps its moves while the other one is played
+                let other = match self.other_game.take() {
+                    Some(other) => other,
+                    None => self.game.reversed(),
+                };
+                self.other_game = Some(std::mem::replace(&mut self.game, other));
+                self.selected_box = None;
+                self.replaying = false;
+                self.game.cancel_plan();
+                self.notice = Some(if self.game.is_reverse() {
+                    "Playing backwards: pull the boxes to where they start".to_owned()
+                } else {
+                    "Playing forwards".to_owned()
+                });
+            }
+            Command::Resume => {
+                if let Some(lurd) = self.resume.take()
+                    && let Some(key) = self.key(ctx)
+                {
+                    // replaying checks that the moves still fit the level file
+                    if let Ok(game) = self.replayed(&lurd) {
+                        self.set_forward_game(game);
+                        self.notice = None;
+                    } else {
+                        ctx.save_data.set_progress(&key, String::new());
+                        ctx.save_data.save();
+                        self.notice =
+                            Some("The saved moves don't fit this level anymore".to_owned());
+                    }
+                }
+            }
+        }
+        self.game_over = self.game.is_game_won();
+        Transition::Stay
+    }
+
+    // Let the solver search on for a frame's slice of time, and use its
+    // result once it has one.
+    fn update_solver(&mut self) {
+        let Some((solver, command, state)) = &mut self.solving else {
+            return;
+        };
+        // the search is for a position the player has left
+        if state != self.game.state() {
+            self.solving = None;
+            self.notice = None;
+            return;
+        }
+        let Some(result) = solver.run(FRAME_SLICE) else {
+            return;
+        };
+        let command = *command;
+        self.solving = None;
+        self.notice = match (command, result) {
+            (Command::Hint, SolverResult::Solved(moves)) => {
+                self.hint =
+                    first_push(self.game.state(), &moves).map(|p| (self.game.state().clone(), p));
+                None
+            }
+            (_, SolverResult::Solved(moves)) => {
+                self.assisted_from = Some(self.game.moves());
+                let replayed = self.game.replay_lurd(&to_lurd(&moves));
+                self.game_over = self.game.is_game_won();
+                replayed.err().map(|e| e.to_string())
+            }
+            (Command::Hint, SolverResult::Unsolvable) => {
+                Some("This position can't be solved anymore".to_owned())
+            }
+            (_, SolverResult::Unsolvable) => Some("This position can't be solved".to_owned()),
+            (Command::Hint, SolverResult::LimitReached) => Some("No hint found in time".to_owned()),
+            (_, SolverResult::LimitReached) => Some("No solution found in time".to_owned()),
+            (_, SolverResult::Failed(position)) => Some(format!(
+                "The solver got stuck at the box on {}, {}",
+                position.x, position.y
+            )),
+        };
+    }
+
+    // Identifies the level in the save data; None for test plays.
+    fn key(&self, ctx: &AppContext<'a>) -> Option<String> {
+        self.level.map(|level| ctx.entries[level].source.key())
+    }
+}
+
+impl<'a> Screen<'a> for PlayScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        let accept_mouse = !ctx.level_chooser.is_open();
+        let gesture = ctx.input_control.get_view_gesture(ctx.camera, accept_mouse);
+        let click = ctx.input_control.get_click(ctx.camera, accept_mouse);
+        if let Some(selected) = ctx.level_chooser.update() {
+            return Transition::PlayLevel(selected);
+        }
+        apply_view_gesture(&mut ctx.game_board, self.game.state(), &gesture);
+        let mut command = ctx
+            .pending_command
+            .take()
+            .or(ctx.input_control.get_command());
+        if self.undo_button.update() {
+            command = Some(Command::Undo);
+        } else if self.redo_button.update() {
+            command = Some(Command::Redo);
+        } else if self.reset_button.update() {
+            command = Some(Command::Reset);
+        } else if self.hint_button.update() {
+            command = Some(Command::Hint);
+        } else if self.resume.is_some() && self.resume_button.update() {
+            command = Some(Command::Resume);
+        } else if self.resume.is_none() && self.menu_button.update() {
+            command = Some(Command::Pause);
+        }
+        if let Some(command) = command {
+            let transition = self.run_command(ctx, command);
+            if !matches!(transition, Transition::Stay) {
+                return transition;
+            }
+        }
+        if let Some(position) = click
+            && !self.game_over
+            && let Some(target) = ctx.game_board.cell_at(self.game.state(), position)
+        {
+            self.notice = None;
+            if self.game.is_reverse() {
+                // before the first pull, the player may start anywhere
+                if !self.game.jump_to(&target) && !self.game.plan_walk(&target) {
+                    self.notice = Some("There is no free way there".to_owned());
+                }
+            } else if self.game.state().is_occupied_by_box(&target)
+                && self.selected_box != Some(target)
+            {
+                self.selected_box = Some(target);
+            } else if let Some(from) = self.selected_box.take() {
+                if from != target && !self.game.plan_push(&from, &target) {
+                    self.notice = Some("The box can't be pushed there".to_owned());
+                }
+            } else if !self.game.plan_walk(&target) {
+                self.notice = Some("There is no free way there".to_owned());
+            }
+        }
+        let current_direction = ctx.input_control.get_direction();
+        if current_direction != DIR_NO_MOVE {
+            self.desired_direction = current_direction;
+            self.game.cancel_plan();
+            self.selected_box = None;
+        }
+        let step_duration = ctx.settings.step_duration();
+        // replays go at the pace of single steps
+        let walk_speed = if self.replaying {
+            step_duration.max(ctx.settings.walk_speed())
+        } else {
+            ctx.settings.walk_speed()
+        };
+        if self.replaying && !self.game.has_plan() {
+            self.replaying = false;
+        }
+        if !self.replaying && !self.game_over {
+            self.play_time += get_frame_time() as f64;
+        }
+        if self.game.has_plan() {
+            if get_time() - self.last_update > walk_speed {
+                self.last_update = get_time();
+                if self.game.perform_planned_move() {
+                    self.game_over = self.game.is_game_won();
+                }
+            }
+        } else if self.desired_direction != DIR_NO_MOVE
+            && get_time() - self.last_update > step_duration
+        {
+            self.last_update = get_time();
+            // player likes to move on this tile:
+            if self.game.try_perform_move(&self.desired_direction) {
+                // check whether move is possible
+                self.game_over = self.game.is_game_won();
+            }
+            self.desired_direction = DIR_NO_MOVE;
+        }
+        self.update_solver();
+
+        // walks are quicker than single steps
+        self.animation.set_duration(if self.game.has_plan() {
+            walk_speed
+        } else {
+            step_duration
+        });
+        self.animation.update(self.game.state(), get_time());
+
+        // a level solved backwards is shown solved forwards, which is what counts
+        if self.game_over
+            && self.game.is_reverse()
+            && !self.animation.is_running(get_time())
+            && let Some(lurd) = self.game.forward_lurd()
+        {
+            let mut game = self.new_game();
+            game.plan_moves(lurd.chars().filter_map(char_to_direction).collect());
+            self.set_forward_game(game);
+            self.game_over = false;
+            self.replaying = true;
+            self.notice = Some("Solved backwards! Here is the solution played forwards".to_owned());
+        }
+
+        // moving on declines the offer to continue
+        if self.resume.is_some() && self.forward_game().moves() > 0 {
+            self.resume = None;
+            if self.notice.as_deref() == Some(RESUME_NOTICE) {
+                self.notice = None;
+            }
+        }
+        if let Some(key) = self.key(ctx)
+            && self.resume.is_none()
+            && self.load_error.is_none()
+            && !self.game_over
+            && get_time() - self.last_autosave > AUTOSAVE_INTERVAL
+        {
+            self.last_autosave = get_time();
+            if ctx.save_data.set_progress(&key, self.forward_game().lurd()) {
+                ctx.save_data.save();
+            }
+        }
+        // undoing the solver's moves makes the level the player's again
+        if self
+            .assisted_from
+            .is_some_and(|moves| self.forward_game().moves() <= moves)
+        {
+            self.assisted_from = None;
+        }
+        if self.game_over
+            && self.load_error.is_none()
+            && !self.game.is_reverse()
+            && !self.was_game_over
+            && let Some(key) = self.key(ctx)
+        {
+            self.previous_best = ctx.save_data.record(&key).copied();
+            if self.assisted_from.is_none() {
+                ctx.save_data
+                    .record_solution(&key, self.game.moves(), self.game.pushes());
+            }
+            ctx.save_data.set_progress(&key, String::new());
+            ctx.save_data.save();
+            if let (Some(level), Some(record)) = (self.level, ctx.save_data.record(&key)) {
+                ctx.level_chooser.set_solved(level, score_text(record));
+            }
+        }
+        self.was_game_over = self.game_over;
+        // the last push is shown before the level counts as won
+        if self.game_over
+            && self.load_error.is_none()
+            && !self.game.is_reverse()
+            && !self.animation.is_running(get_time())
+        {
+            let solution = Solution {
+                level: self.level,
+                start: self.game.initial_state().clone(),
+                title: self.game.title().to_owned(),
+                author: self.game.author().to_owned(),
+                lurd: self.game.lurd(),
+                moves: self.game.moves(),
+                pushes: self.game.pushes(),
+                time: self.play_time,
+                previous_best: self.previous_best,
+                assisted_from: self.assisted_from,
+            };
+            let win = WinScreen::new(ctx, solution);
+            return Transition::Push(Box::new(win));
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        if let Some(error) = &self.load_error {
+            draw_load_error("This level could not be loaded:", error, ctx.fonts);
+        } else {
+            let game_board = &ctx.game_board;
+            let state = self.game.state();
+            game_board.draw_board(state, self.game.deadlocked_boxes(), &self.animation);
+            if !self.game.deadlocked_boxes().is_empty() {
+                game_board.draw_lost_notice(ctx.fonts);
+            }
+            if let Some((hint_state, push)) = &self.hint
+                && hint_state == state
+            {
+                game_board.draw_hint(state, &push.box_position, &push.direction);
+            }
+            if let Some(box_position) = &self.selected_box {
+                game_board.draw_selection(state, box_position);
+            }
+            game_board.draw_margins();
+        }
+        draw_status_bar(&self.game, self.notice.as_deref(), ctx.fonts);
+        self.undo_button.draw();
+        self.redo_button.draw();
+        self.reset_button.draw();
+        self.hint_button.draw();
+        if self.resume.is_some() {
+            self.resume_button.draw();
+        } else {
+            self.menu_button.draw();
+        }
+        ctx.level_chooser.draw();
+    }
+
+    // keep the moves of the level left, unless they were never restored
+    fn leave(&mut self, ctx: &mut AppContext<'a>) {
+        if let Some(key) = self.key(ctx)
+            && self.resume.is_none()
+            && self.load_error.is_none()
+            && !self.game_over
+            && ctx.save_data.set_progress(&key, self.forward_game().lurd())
+        {
+            ctx.save_data.save();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Run-length encoding of boards and LURD strings, e.g. "3#2-$|#@.#":
+// a count repeats the next character or (group), '|' separates rows.
+
+use std::fmt;
+use std::io;
+
+use crate::game_logic::{GameState, Point};
+use crate::level_writer::cell_char;
+
+const ROW_SEPARATOR: char = '|';
+// largest count allowed, and the longest text decoding may produce
+const MAX_COUNT: usize = 10_000;
+const MAX_DECODED_LENGTH: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RleError {
+    // a count above MAX_COUNT, at the given character position
+    CountTooLarge(usize),
+    // the decoded text would be longer than MAX_DECODED_LENGTH
+    TooLong,
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::CountTooLarge(index) => {
+                write!(
+                    f,
+                    "Count too large at position {index}, at most {MAX_COUNT}"
+                )
+            }
+            RleError::TooLong => write!(f, "Run-length encoded text is too long when expanded"),
+        }
+    }
+}
+
+// Characters that may only appear in run-length encoded text.
+pub fn is_rle_char(c: char) -> bool {
+    c.is_ascii_digit() || c == ROW_SEPARATOR || c == '(' || c == ')'
+}
+
+// Expand counts and groups; row separators become line breaks. Text without
+// any RLE characters is returned unchanged.
+pub fn decode_rle(text: &str) -> Result<String, RleError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pos = 0;
+    let mut decoded = String::new();
+    // an unmatched ')' is kept, so the caller can report it
+    while pos < chars.len() {
+        let budget = MAX_DECODED_LENGTH - decoded.len();
+        decoded += &expand(&chars, &mut pos, budget)?;
+        if pos < chars.len() {
+            decoded.push(chars[pos]);
+            pos += 1;
+        }
+        if decoded.len() > MAX_DECODED_LENGTH {
+            return Err(RleError::TooLong);
+        }
+    }
+    Ok(decoded)
+}
+
+// Expand until the end of the text or the ')' closing the current group;
+// the result may be at most `budget` bytes long.
+fn expand(chars: &[char], pos: &mut usize, budget: usize) -> Result<String, RleError> {
+    let mut expanded = String::new();
+    while *pos < chars.len() && chars[*pos] != ')' {
+        let start = *pos;
+        let mut count: usize = 0;
+        let mut has_count = false;
+        while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+            count = count
+                .checked_mul(10)
+                .and_then(|count| count.checked_add(chars[*pos].to_digit(10)? as usize))
+                .filter(|count| *count <= MAX_COUNT)
+                .ok_or(RleError::CountTooLarge(start))?;
+            has_count = true;
+            *pos += 1;
+        }
+        if !has_count {
+            count = 1;
+        }
+        let Some(&c) = chars.get(*pos) else {
+            break;
+        };
+        *pos += 1;
+        let part = if c == '(' {
+            let group = expand(chars, pos, budget - expanded.len())?;
+            // skip the closing ')'
+            *pos += 1;
+            group
+        } else if c == ROW_SEPARATOR {
+            String::from('\n')
+        } else {
+            String::from(c)
+        };
+        if part
+            .len()
+            .checked_mul(count)
+            .is_none_or(|length| expanded.len() + length > budget)
+        {
+            return Err(RleError::TooLong);
+        }
+        expanded += &part.repeat(count);
+    }
+    Ok(expanded)
+}
+
+// Replace every run of two or more equal characters by count and character.
+pub fn encode_rle(text: &str) -> String {
+    let mut encoded = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut count = 1;
+        while chars.next_if_eq(&c).is_some() {
+            count += 1;
+        }
+        if count > 1 {
+            encoded += &count.to_string();
+        }
+        encoded.push(c);
+    }
+    encoded
+}
+
+// The board as RLE string; floor is written as '-', trailing floor is left out.
+pub fn state_to_rle(state: &GameState) -> io::Result<String> {
+    let mut rows = Vec::new();
+    for y in 0..state.height() {
+        let row: String = (0..state.width())
+            .map(|x| {
+                cell_char(state, &Point { x, y }).map(|c| match c {
+                    ' ' => '-',
+                    c => c,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        rows.push(encode_rle(row.trim_end_matches('-')));
+    }
+    Ok(rows.join(&ROW_SEPARATOR.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::GameCell;
+
+    #[test]
+    fn decodes_counts_and_groups() {
+        assert_eq!(decode_rle("3#|2(-$)").unwrap(), "###\n-$-$");
+        assert_eq!(decode_rle("luRD").unwrap(), "luRD");
+    }
+
+    #[test]
+    fn huge_count_is_an_error() {
+        assert_eq!(
+            decode_rle("ul99999999999999999999999r"),
+            Err(RleError::CountTooLarge(2))
+        );
+    }
+
+    #[test]
+    fn nested_groups_stay_bounded() {
+        assert_eq!(decode_rle("9999(9999(9999(r)))"), Err(RleError::TooLong));
+    }
+
+    #[test]
+    fn player_on_box_is_not_written() {
+        let mut state = GameState::new(3, 1);
+        state.set_cell(&Point { x: 1, y: 0 }, GameCell::Box);
+        state.set_player_position(&Point { x: 0, y: 0 });
+        assert_eq!(state_to_rle(&state).unwrap(), "@$");
+        state.set_player_position(&Point { x: 1, y: 0 });
+        assert!(state_to_rle(&state).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Progress that survives a restart: solved levels and their best scores, the
+// moves made in unfinished levels, the level played last and the settings.
+// Natively stored in a file in the config directory, on the web in the
+// browser's localStorage (see web/kanjiban_storage.js).
+//
+// The format is text, one record per line, fields separated by tabs:
+//   solved <level key> <moves> <pushes>   (of the best solution)
+//   optimal <level key> <fewest pushes possible, '-' if the solver gave up>
+//   progress <level key> <moves as LURD>
+//   last <level key>
+//   setting <name> <value>
+// Lines of unknown kind are skipped, so newer versions can add records.
+
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+const STORAGE_KEY: &str = "kanjiban_save";
+
+// Moves and pushes of the best solution of a level.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct LevelRecord {
+    pub moves: u64,
+    pub pushes: u64,
+}
+
+impl LevelRecord {
+    // Fewer pushes are better; of equally many pushes, fewer moves.
+    pub fn is_better_than(&self, other: &LevelRecord) -> bool {
+        (self.pushes, self.moves) < (other.pushes, other.moves)
+    }
+}
+
+pub struct SaveData {
+    // keyed by the level key, see LevelSource::key
+    records: HashMap<String, LevelRecord>,
+    optimal_pushes: HashMap<String, Option<u64>>,
+    progress: HashMap<String, String>,
+    last_level: Option<String>,
+    settings: HashMap<String, String>,
+}
+
+impl SaveData {
+    // Read the stored progress; missing or broken data gives empty progress.
+    pub fn load() -> Self {
+        Self::parse(&read_storage().unwrap_or_default())
+    }
+
+    // Progress from its text form; broken lines are skipped.
+    fn parse(contents: &str) -> Self {
+        let mut save_data = Self {
+            records: HashMap::new(),
+            optimal_pushes: HashMap::new(),
+            progress: HashMap::new(),
+            last_level: None,
+            settings: HashMap::new(),
+        };
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["solved", key, moves, pushes] => {
+                    if let (Ok(moves), Ok(pushes)) = (moves.parse(), pushes.parse()) {
+                        save_data
+                            .records
+                            .insert(key.to_owned(), LevelRecord { moves, pushes });
+                    }
+                }
+                ["optimal", key, "-"] => {
+                    save_data.optimal_pushes.insert(key.to_owned(), None);
+                }
+                ["optimal", key, pushes] => {
+                    if let Ok(pushes) = pushes.parse() {
+                        save_data
+                            .optimal_pushes
+                            .insert(key.to_owned(), Some(pushes));
+                    }
+                }
+                ["progress", key, lurd] => {
+                    save_data.progress.insert(key.to_owned(), lurd.to_owned());
+                }
+                ["last", key] => save_data.last_level = Some(key.to_owned()),
+                ["setting", name, value] => {
+                    save_data.settings.insert(name.to_owned(), value.to_owned());
+                }
+                _ => {}
+            }
+        }
+        save_data
+    }
+
+    pub fn save(&self) {
+        write_storage(&self.to_text());
+    }
+
+    // The text form of the progress, see the top of the file.
+    fn to_text(&self) -> String {
+        let mut keys: Vec<&String> = self.records.keys().collect();
+        keys.sort();
+        let mut contents = String::new();
+        for key in keys {
+            let record = &self.records[key];
+            contents += &format!("solved\t{}\t{}\t{}\n", key, record.moves, record.pushes);
+        }
+        let mut keys: Vec<&String> = self.optimal_pushes.keys().collect();
+        keys.sort();
+        for key in keys {
+            let pushes = match self.optimal_pushes[key] {
+                Some(pushes) => pushes.to_string(),
+                None => "-".to_owned(),
+            };
+            contents += &format!("optimal\t{key}\t{pushes}\n");
+        }
+        let mut keys: Vec<&String> = self.progress.keys().collect();
+        keys.sort();
+        for key in keys {
+            contents += &format!("progress\t{}\t{}\n", key, self.progress[key]);
+        }
+        if let Some(key) = &self.last_level {
+            contents += &format!("last\t{key}\n");
+        }
+        let mut names: Vec<&String> = self.settings.keys().collect();
+        names.sort();
+        for name in names {
+            contents += &format!("setting\t{}\t{}\n", name, self.settings[name]);
+        }
+        contents
+    }
+
+    pub fn record(&self, key: &str) -> Option<&LevelRecord> {
+        self.records.get(key)
+    }
+
+    // Remember a solution if it is better than the best one so far.
+    pub fn record_solution(&mut self, key: &str, moves: u64, pushes: u64) {
+        let solution = LevelRecord { moves, pushes };
+        if self
+            .records
+            .get(key)
+            .is_none_or(|best| solution.is_better_than(best))
+        {
+            self.records.insert(key.to_owned(), solution);
+        }
+    }
+
+    // The fewest pushes that solve a level: None if the solver hasn't been
+    // run on the level yet, Some(None) if it gave up.
+    pub fn optimal_pushes(&self, key: &str) -> Option<Option<u64>> {
+        self.optimal_pushes.get(key).copied()
+    }
+
+    pub fn set_optimal_pushes(&mut self, key: &str, pushes: Option<u64>) {
+        self.optimal_pushes.insert(key.to_owned(), pushes);
+    }
+
+    // The moves made so far in an unfinished level.
+    pub fn progress(&self, key: &str) -> Option<&str> {
+        self.progress.get(key).map(String::as_str)
+    }
+
+    // Remember the moves made in a level; no moves forget the level's progress.
+    // Returns true iff this changed anything.
+    pub fn set_progress(&mut self, key: &str, lurd: String) -> bool {
+        if lurd.is_empty() {
+            self.progress.remove(key).is_some()
+        } else if self.progress(key) == Some(lurd.as_str()) {
+            false
+        } else {
+            self.progress.insert(key.to_owned(), lurd);
+            true
+        }
+    }
+
+    pub fn last_level(&self) -> Option<&str> {
+        self.last_level.as_deref()
+    }
+
+    pub fn set_last_level(&mut self, key: String) {
+        self.last_level = Some(key);
+    }
+
+    pub fn setting(&self, name: &str) -> Option<&str> {
+        self.settings.get(name).map(String::as_str)
+    }
+
+    pub fn set_setting(&mut self, name: &str, value: String) {
+        self.settings.insert(name.to_owned(), value);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path() -> Option<std::path::PathBuf> {
+    use std::env::var_os;
+    use std::path::PathBuf;
+    let config_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(var_os("HOME")?).join(".config"),
+        }
+    };
+    Some(
+        config_dir
+            .join("kanjiban")
+            .join(format!("{STORAGE_KEY}.txt")),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_storage() -> Option<String> {
+    std::fs::read_to_string(save_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_storage(contents: &str) {
+    let Some(path) = save_path() else {
+        warn!("No config directory to save progress in");
+        return;
+    };
+    // write to a temporary file first, so a crash can't leave half a file
+    let temporary = path.with_extension("tmp");
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&temporary, contents))
+        .and_then(|_| std::fs::rename(&temporary, &path));
+    if let Err(error) = result {
+        warn!("Can't save progress to {}: {}", path.display(), error);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    fn kanjiban_storage_set(key: *const u8, key_len: u32, value: *const u8, value_len: u32);
+    // length of the stored value in bytes, -1 if there is none
+    fn kanjiban_storage_len(key: *const u8, key_len: u32) -> i32;
+    fn kanjiban_storage_get(key: *const u8, key_len: u32, buffer: *mut u8, buffer_len: u32);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_storage() -> Option<String> {
+    let key = STORAGE_KEY.as_bytes();
+    // SAFETY: the plugin only reads the key and writes at most buffer_len bytes
+    unsafe {
+        let len = kanjiban_storage_len(key.as_ptr(), key.len() as u32);
+        if len < 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; len as usize];
+        kanjiban_storage_get(
+            key.as_ptr(),
+            key.len() as u32,
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        );
+        String::from_utf8(buffer).ok()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_storage(contents: &str) {
+    let key = STORAGE_KEY.as_bytes();
+    // SAFETY: the plugin only reads the given ranges
+    unsafe {
+        kanjiban_storage_set(
+            key.as_ptr(),
+            key.len() as u32,
+            contents.as_ptr(),
+            contents.len() as u32,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVED: &str = "solved\tlevels/a.lvl\t30\t5\n\
+                         optimal\tlevels/a.lvl\t4\n\
+                         optimal\tpack.txt#2\t-\n\
+                         progress\tpack.txt#2\tluRD\n\
+                         last\tpack.txt#2\n\
+                         setting\tsound\toff\n";
+
+    #[test]
+    fn text_reads_back() {
+        let save_data = SaveData::parse(SAVED);
+        let record = save_data.record("levels/a.lvl").unwrap();
+        assert_eq!((record.moves, record.pushes), (30, 5));
+        assert_eq!(save_data.optimal_pushes("levels/a.lvl"), Some(Some(4)));
+        assert_eq!(save_data.optimal_pushes("pack.txt#2"), Some(None));
+        assert_eq!(save_data.optimal_pushes("levels/b.lvl"), None);
+        assert_eq!(save_data.progress("pack.txt#2"), Some("luRD"));
+        assert_eq!(save_data.last_level(), Some("pack.txt#2"));
+        assert_eq!(save_data.setting("sound"), Some("off"));
+        assert_eq!(save_data.to_text(), SAVED);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let save_data = SaveData::parse(
+            "solved\ta.lvl\tmany\t5\nsolved\tb.lvl\t3\noptimal\ta.lvl\t?\nnew\tkind\nlast\n",
+        );
+        assert!(save_data.record("a.lvl").is_none());
+        assert!(save_data.record("b.lvl").is_none());
+        assert_eq!(save_data.optimal_pushes("a.lvl"), None);
+        assert_eq!(save_data.last_level(), None);
+        assert_eq!(save_data.to_text(), "");
+    }
+
+    #[test]
+    fn best_solution_is_kept_whole() {
+        let mut save_data = SaveData::parse("");
+        save_data.record_solution("a.lvl", 30, 5);
+        save_data.record_solution("a.lvl", 20, 6);
+        save_data.record_solution("a.lvl", 40, 4);
+        save_data.record_solution("a.lvl", 35, 4);
+        save_data.record_solution("a.lvl", 50, 4);
+        let record = save_data.record("a.lvl").unwrap();
+        assert_eq!((record.moves, record.pushes), (35, 4));
+    }
+}
@@ -0,0 +1,68 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Settings menu; choosing a setting cycles through its values, which are
+// stored with the save data right away.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{ANIMATION_SPEEDS, AppContext, Screen, Settings, Transition, WALK_SPEEDS};
+use crate::menu::{Menu, draw_heading};
+
+const ANIMATION_ITEM: usize = 0;
+const WALK_ITEM: usize = 1;
+const AUTO_ADVANCE_ITEM: usize = 2;
+const BACK_ITEM: usize = 3;
+
+fn labels(settings: &Settings) -> [String; 4] {
+    [
+        format!("Animation: {}", ANIMATION_SPEEDS[settings.animation].0),
+        format!("Walking: {}", WALK_SPEEDS[settings.walk].0),
+        format!(
+            "Auto-advance: {}",
+            if settings.auto_advance { "On" } else { "Off" }
+        ),
+        "Back".to_owned(),
+    ]
+}
+
+pub struct SettingsScreen<'a> {
+    menu: Menu<'a>,
+}
+
+impl<'a> SettingsScreen<'a> {
+    pub fn new(ctx: &AppContext<'a>) -> Self {
+        Self {
+            menu: Menu::new(ctx.camera, 300., labels(&ctx.settings).into(), ctx.fonts),
+        }
+    }
+}
+
+impl<'a> Screen<'a> for SettingsScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop(1);
+        }
+        let settings = &mut ctx.settings;
+        match self.menu.update() {
+            Some(ANIMATION_ITEM) => {
+                settings.animation = (settings.animation + 1) % ANIMATION_SPEEDS.len();
+            }
+            Some(WALK_ITEM) => settings.walk = (settings.walk + 1) % WALK_SPEEDS.len(),
+            Some(AUTO_ADVANCE_ITEM) => settings.auto_advance = !settings.auto_advance,
+            Some(BACK_ITEM) => return Transition::Pop(1),
+            _ => return Transition::Stay,
+        }
+        settings.store(&mut ctx.save_data);
+        ctx.save_data.save();
+        for (index, label) in labels(&ctx.settings).into_iter().enumerate() {
+            self.menu.set_item(index, label);
+        }
+        Transition::Stay
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        clear_background(LIGHTGRAY);
+        draw_heading("Settings", 200., 70, ctx.fonts);
+        self.menu.draw();
+    }
+}
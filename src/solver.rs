@@ -0,0 +1,524 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Headless Sokoban solver: A* over box pushes. The estimate greedily matches
+// boxes to sinks by push distance, so solutions are near push-optimal; the
+// slower optimal search only counts the distance to the nearest sink, which
+// never overestimates. The result is a full move list (walks and pushes) that
+// can be replayed on the game. The search runs in slices of time, so it can be
+// spread over frames.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use macroquad::miniquad::date;
+
+use crate::deadlock::{Board, is_frozen, push_distances, sinks};
+use crate::game_logic::{GameCell, GameState, Move, Point};
+use crate::input_control::{DIR_DOWN, DIR_LEFT, DIR_RIGHT, DIR_UP};
+
+const DIRECTIONS: [Point; 4] = [DIR_LEFT, DIR_UP, DIR_RIGHT, DIR_DOWN];
+const UNREACHABLE: u32 = u32::MAX;
+// seconds per frame a search may take when spread over frames
+pub const FRAME_SLICE: f64 = 0.01;
+
+pub struct SolverLimits {
+    pub max_nodes: usize,
+    pub max_seconds: f64,
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: 500_000,
+            max_seconds: 5.0,
+        }
+    }
+}
+
+// A single push: the box to move and the direction to push it.
+pub struct Push {
+    pub box_position: Point,
+    pub direction: Point,
+}
+
+pub enum SolverResult {
+    // solution starting from the given state
+    Solved(Vec<Move>),
+    Unsolvable,
+    LimitReached,
+    // the pushes found couldn't be replayed from the box on this position;
+    // a bug in the solver
+    Failed(Point),
+}
+
+// Static part of the level. Cells are indexed on a grid padded with a border
+// of walls, so neighbours never leave the grid.
+struct Level {
+    width: usize,
+    offsets: [isize; 4],
+    floor: Vec<bool>,
+    sink: Vec<bool>,
+    // per sink: push distance from every cell
+    distances: Vec<Vec<u32>>,
+    // minimum over all sinks
+    nearest: Vec<u32>,
+}
+
+impl Level {
+    fn new(state: &GameState) -> Self {
+        let width = state.width() as usize + 2;
+        let size = width * (state.height() as usize + 2);
+        let mut level = Self {
+            width,
+            offsets: DIRECTIONS.map(|d| d.y as isize * width as isize + d.x as isize),
+            floor: vec![false; size],
+            sink: vec![false; size],
+            distances: Vec::new(),
+            nearest: vec![UNREACHABLE; size],
+        };
+        for y in 0..state.height() {
+            for x in 0..state.width() {
+                let pos = Point { x, y };
+                let index = level.index(&pos);
+                level.floor[index] = state.get_cell(&pos) != GameCell::Unmovable;
+            }
+        }
+        for sink in sinks(state) {
+            let index = level.index(&sink);
+            level.sink[index] = true;
+            let mut distances = vec![UNREACHABLE; size];
+            for (i, d) in push_distances(state, &[sink]).into_iter().enumerate() {
+                let pos = Point {
+                    x: i as i32 % state.width(),
+                    y: i as i32 / state.width(),
+                };
+                let index = level.index(&pos);
+                distances[index] = d.unwrap_or(UNREACHABLE);
+            }
+            for (nearest, d) in level.nearest.iter_mut().zip(&distances) {
+                *nearest = (*nearest).min(*d);
+            }
+            level.distances.push(distances);
+        }
+        level
+    }
+
+    fn index(&self, p: &Point) -> usize {
+        (p.y as usize + 1) * self.width + p.x as usize + 1
+    }
+
+    fn point(&self, index: usize) -> Point {
+        Point {
+            x: (index % self.width) as i32 - 1,
+            y: (index / self.width) as i32 - 1,
+        }
+    }
+
+    fn step(&self, index: usize, direction: usize) -> usize {
+        (index as isize + self.offsets[direction]) as usize
+    }
+
+    // Pushes still needed: with greedy matching of boxes to sinks by push
+    // distance, or, if optimal, the sum of the distances to the nearest sinks.
+    // None if a box can't reach any sink at all.
+    fn estimate(&self, boxes: &[u32], optimal: bool) -> Option<u32> {
+        if optimal {
+            return boxes.iter().try_fold(0, |total, box_index| {
+                let nearest = self.nearest[*box_index as usize];
+                (nearest != UNREACHABLE).then_some(total + nearest)
+            });
+        }
+        let mut pairs = Vec::with_capacity(boxes.len() * self.distances.len());
+        for (b, box_index) in boxes.iter().enumerate() {
+            if self.nearest[*box_index as usize] == UNREACHABLE {
+                return None;
+            }
+            for (s, distances) in self.distances.iter().enumerate() {
+                let distance = distances[*box_index as usize];
+                if distance != UNREACHABLE {
+                    pairs.push((distance, b, s));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        let mut box_done = vec![false; boxes.len()];
+        let mut sink_done = vec![false; self.distances.len()];
+        let mut total = 0;
+        for (distance, b, s) in pairs {
+            if !box_done[b] && !sink_done[s] {
+                box_done[b] = true;
+                sink_done[s] = true;
+                total += distance;
+            }
+        }
+        // boxes left over by the greedy matching count with their nearest sink
+        for (b, box_index) in boxes.iter().enumerate() {
+            if !box_done[b] {
+                total += self.nearest[*box_index as usize];
+            }
+        }
+        Some(total)
+    }
+
+    // Cells the player can walk to without pushing; also returns the smallest
+    // such cell, used as normalized player position.
+    fn reachable(&self, occupied: &[bool], player: usize, visited: &mut [bool]) -> usize {
+        visited.fill(false);
+        visited[player] = true;
+        let mut smallest = player;
+        let mut queue = VecDeque::from([player]);
+        while let Some(current) = queue.pop_front() {
+            for direction in 0..4 {
+                let next = self.step(current, direction);
+                if self.floor[next] && !occupied[next] && !visited[next] {
+                    visited[next] = true;
+                    smallest = smallest.min(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        smallest
+    }
+}
+
+// The level with the cells occupied by boxes during the search.
+struct Position<'a>(&'a Level, &'a [bool]);
+
+impl Board for Position<'_> {
+    type Cell = usize;
+
+    fn axes(&self, index: usize) -> [(usize, usize); 2] {
+        [0, 1].map(|axis| (self.0.step(index, axis), self.0.step(index, axis + 2)))
+    }
+
+    fn is_wall(&self, index: usize) -> bool {
+        !self.0.floor[index]
+    }
+
+    fn is_dead_square(&self, index: usize) -> bool {
+        self.0.nearest[index] == UNREACHABLE
+    }
+
+    fn is_occupied_by_box(&self, index: usize) -> bool {
+        self.1[index]
+    }
+}
+
+struct Node {
+    boxes: Vec<u32>,
+    // player position right after the push
+    player: u32,
+    parent: usize,
+    // box position before the push and push direction
+    push: Option<(u32, u8)>,
+}
+
+// A search that can be run a little at a time, e.g. a slice per frame, so
+// the game stays responsive while it goes on.
+pub struct Solver {
+    start: GameState,
+    level: Level,
+    limits: SolverLimits,
+    optimal: bool,
+    // seconds searched so far
+    spent: f64,
+    nodes: Vec<Node>,
+    // (estimated total pushes, pushes so far, node index); prefers deeper
+    // nodes among equal estimates
+    open: BinaryHeap<(Reverse<u32>, u32, Reverse<usize>)>,
+    // pushes needed to reach each expanded position, keyed by the boxes and
+    // the normalized player position
+    closed: HashMap<(Vec<u32>, u32), u32>,
+    occupied: Vec<bool>,
+    visited: Vec<bool>,
+    walls: Vec<usize>,
+}
+
+impl Solver {
+    pub fn new(start: &GameState, limits: SolverLimits) -> Self {
+        Self::with_estimate(start, limits, false)
+    }
+
+    // Like new, but the solution has the fewest pushes possible.
+    pub fn optimal(start: &GameState, limits: SolverLimits) -> Self {
+        Self::with_estimate(start, limits, true)
+    }
+
+    fn with_estimate(start: &GameState, limits: SolverLimits, optimal: bool) -> Self {
+        let level = Level::new(start);
+        // box positions are stored as u32 on the padded grid
+        assert!(
+            u32::try_from(level.floor.len()).is_ok(),
+            "level too large for the solver"
+        );
+        let mut boxes: Vec<u32> = Vec::new();
+        for y in 0..start.height() {
+            for x in 0..start.width() {
+                let pos = Point { x, y };
+                if start.is_occupied_by_box(&pos) {
+                    boxes.push(level.index(&pos) as u32);
+                }
+            }
+        }
+        // with nothing to expand, the search ends as unsolvable
+        let mut open = BinaryHeap::new();
+        if boxes.len() <= level.distances.len()
+            && let Some(estimate) = level.estimate(&boxes, optimal)
+        {
+            open.push((Reverse(estimate), 0, Reverse(0)));
+        }
+        let size = level.floor.len();
+        Self {
+            start: start.clone(),
+            nodes: vec![Node {
+                boxes,
+                player: level.index(&start.get_player_position()) as u32,
+                parent: 0,
+                push: None,
+            }],
+            level,
+            limits,
+            optimal,
+            spent: 0.,
+            open,
+            closed: HashMap::new(),
+            occupied: vec![false; size],
+            visited: vec![false; size],
+            walls: Vec::new(),
+        }
+    }
+
+    // Search for about the given number of seconds at most; returns the
+    // result once the search is over, None if it needs more time.
+    pub fn run(&mut self, seconds: f64) -> Option<SolverResult> {
+        let started_at = date::now();
+        let mut expanded: usize = 0;
+        while let Some((_, pushes, Reverse(node_index))) = self.open.pop() {
+            if let Some(result) = self.expand(node_index, pushes) {
+                return Some(result);
+            }
+            expanded += 1;
+            if expanded.is_multiple_of(256) {
+                let elapsed = date::now() - started_at;
+                if self.spent + elapsed > self.limits.max_seconds {
+                    return Some(SolverResult::LimitReached);
+                }
+                if elapsed > seconds {
+                    self.spent += elapsed;
+                    return None;
+                }
+            }
+        }
+        Some(SolverResult::Unsolvable)
+    }
+
+    // Expand a node of the open list; returns the result if the search is over.
+    fn expand(&mut self, node_index: usize, pushes: u32) -> Option<SolverResult> {
+        let level = &self.level;
+        let occupied = &mut self.occupied;
+        let boxes = self.nodes[node_index].boxes.clone();
+        if boxes.iter().all(|b| level.sink[*b as usize]) {
+            return Some(
+                match solution_moves(&self.start, level, &self.nodes, node_index) {
+                    Ok(moves) => SolverResult::Solved(moves),
+                    Err(error) => SolverResult::Failed(error),
+                },
+            );
+        }
+        occupied.fill(false);
+        for b in &boxes {
+            occupied[*b as usize] = true;
+        }
+        let player = level.reachable(
+            occupied,
+            self.nodes[node_index].player as usize,
+            &mut self.visited,
+        );
+        let key = (boxes, player as u32);
+        if self.closed.get(&key).is_some_and(|known| *known <= pushes) {
+            return None; // already expanded with fewer pushes
+        }
+        let boxes = key.0.clone();
+        self.closed.insert(key, pushes);
+        if self.nodes.len() > self.limits.max_nodes {
+            return Some(SolverResult::LimitReached);
+        }
+        for (i, box_index) in boxes.iter().enumerate() {
+            let box_index = *box_index as usize;
+            for direction in 0..4 {
+                let target = level.step(box_index, direction);
+                let behind = level.step(box_index, (direction + 2) % 4);
+                if !self.visited[behind]
+                    || !level.floor[target]
+                    || occupied[target]
+                    || level.nearest[target] == UNREACHABLE
+                {
+                    continue;
+                }
+                occupied[box_index] = false;
+                occupied[target] = true;
+                let frozen = !level.sink[target]
+                    && is_frozen(&Position(level, occupied), target, &mut self.walls);
+                occupied[target] = false;
+                occupied[box_index] = true;
+                if frozen {
+                    continue;
+                }
+                let mut next_boxes = boxes.clone();
+                next_boxes[i] = target as u32;
+                next_boxes.sort_unstable();
+                let Some(estimate) = level.estimate(&next_boxes, self.optimal) else {
+                    continue;
+                };
+                let next_pushes = pushes + 1;
+                self.open.push((
+                    Reverse(next_pushes + estimate),
+                    next_pushes,
+                    Reverse(self.nodes.len()),
+                ));
+                self.nodes.push(Node {
+                    boxes: next_boxes,
+                    player: box_index as u32,
+                    parent: node_index,
+                    push: Some((box_index as u32, direction as u8)),
+                });
+            }
+        }
+        None
+    }
+}
+
+// The first push of a move list performed from the given state.
+pub fn first_push(start: &GameState, moves: &[Move]) -> Option<Push> {
+    let mut player = start.get_player_position();
+    for performed in moves {
+        if performed.pushed {
+            return Some(Push {
+                box_position: player + performed.direction,
+                direction: performed.direction,
+            });
+        }
+        player = player + performed.direction;
+    }
+    None
+}
+
+// Turn the pushes leading to the node into player moves, walking the
+// player to the right side of each box in between. Fails with the box
+// position of the first push that can't be made.
+fn solution_moves(
+    start: &GameState,
+    level: &Level,
+    nodes: &[Node],
+    goal: usize,
+) -> Result<Vec<Move>, Point> {
+    let mut pushes = Vec::new();
+    let mut node_index = goal;
+    while let Some(push) = nodes[node_index].push {
+        pushes.push(push);
+        node_index = nodes[node_index].parent;
+    }
+    pushes.reverse();
+    let mut state = start.clone();
+    let mut moves = Vec::new();
+    for (box_index, direction) in pushes {
+        let direction = DIRECTIONS[direction as usize];
+        let box_pos = level.point(box_index as usize);
+        let walk = state.find_path(&(box_pos - direction)).ok_or(box_pos)?;
+        for step in walk.iter().chain(std::iter::once(&direction)) {
+            moves.push(state.try_perform_move(step).ok_or(box_pos)?);
+        }
+        if !moves.last().is_some_and(|m| m.pushed) {
+            return Err(box_pos);
+        }
+    }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::Game;
+    use crate::level_loader::LevelLoader;
+    use crate::lurd::to_lurd;
+
+    fn run(solver: &mut Solver) -> SolverResult {
+        solver
+            .run(f64::INFINITY)
+            .expect("an unlimited run ends the search")
+    }
+
+    fn assert_solves(mut game: Game) {
+        let mut solver = Solver::new(game.state(), SolverLimits::default());
+        let SolverResult::Solved(moves) = run(&mut solver) else {
+            panic!("no solution found");
+        };
+        game.replay_lurd(&to_lurd(&moves)).unwrap();
+        assert!(game.is_game_won());
+    }
+
+    #[test]
+    fn solves_level_file() {
+        let contents = std::fs::read_to_string("levels/level_0.lvl").unwrap();
+        assert_solves(LevelLoader::parse_str(&contents).unwrap());
+    }
+
+    #[test]
+    fn solves_pack_level() {
+        let contents = std::fs::read_to_string("levels/kanjiban_pack.txt").unwrap();
+        let collection = LevelLoader::parse_collection_str(&contents).unwrap();
+        for game in collection.levels {
+            assert_solves(game);
+        }
+    }
+
+    #[test]
+    fn finds_fewest_pushes() {
+        let contents = std::fs::read_to_string("levels/level_0.lvl").unwrap();
+        let game = LevelLoader::parse_str(&contents).unwrap();
+        let mut solver = Solver::optimal(game.state(), SolverLimits::default());
+        let SolverResult::Solved(moves) = run(&mut solver) else {
+            panic!("no solution found");
+        };
+        assert_eq!(moves.iter().filter(|m| m.pushed).count(), 5);
+    }
+
+    #[test]
+    fn box_in_corner_is_unsolvable() {
+        let game = LevelLoader::parse_str("#####\n#$  #\n# @.#\n#####\n").unwrap();
+        let mut solver = Solver::new(game.state(), SolverLimits::default());
+        assert!(matches!(run(&mut solver), SolverResult::Unsolvable));
+    }
+
+    #[test]
+    fn runs_in_slices() {
+        let contents = std::fs::read_to_string("levels/level_3.lvl").unwrap();
+        let mut game = LevelLoader::parse_str(&contents).unwrap();
+        let mut solver = Solver::new(game.state(), SolverLimits::default());
+        let mut slices = 1;
+        let result = loop {
+            match solver.run(0.) {
+                Some(result) => break result,
+                None => slices += 1,
+            }
+        };
+        assert!(slices > 1);
+        let SolverResult::Solved(moves) = result else {
+            panic!("no solution found");
+        };
+        game.replay_lurd(&to_lurd(&moves)).unwrap();
+        assert!(game.is_game_won());
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let contents = std::fs::read_to_string("levels/level_1.lvl").unwrap();
+        let game = LevelLoader::parse_str(&contents).unwrap();
+        let limits = SolverLimits {
+            max_nodes: 10,
+            max_seconds: 5.0,
+        };
+        let mut solver = Solver::new(game.state(), limits);
+        assert!(matches!(run(&mut solver), SolverResult::LimitReached));
+    }
+}
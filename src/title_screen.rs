@@ -0,0 +1,62 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Title menu, the screen the game starts with.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::level_select_screen::LevelSelectScreen;
+use crate::menu::{Menu, draw_heading};
+use crate::settings_screen::SettingsScreen;
+
+#[derive(Clone, Copy)]
+enum TitleAction {
+    Play,
+    ChooseLevel,
+    Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
+}
+
+const ACTIONS: &[(&str, TitleAction)] = &[
+    ("Play", TitleAction::Play),
+    ("Choose level", TitleAction::ChooseLevel),
+    ("Settings", TitleAction::Settings),
+    // a web page can't be quit
+    #[cfg(not(target_arch = "wasm32"))]
+    ("Quit", TitleAction::Quit),
+];
+
+pub struct TitleScreen<'a> {
+    menu: Menu<'a>,
+}
+
+impl<'a> TitleScreen<'a> {
+    pub fn new(ctx: &AppContext<'a>) -> Self {
+        let items = ACTIONS.iter().map(|(label, _)| label.to_string()).collect();
+        Self {
+            menu: Menu::new(ctx.camera, 300., items, ctx.fonts),
+        }
+    }
+}
+
+impl<'a> Screen<'a> for TitleScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        let Some(index) = self.menu.update() else {
+            return Transition::Stay;
+        };
+        match ACTIONS[index].1 {
+            TitleAction::Play => Transition::PlayLevel(ctx.current_level),
+            TitleAction::ChooseLevel => Transition::Push(Box::new(LevelSelectScreen::new(ctx))),
+            TitleAction::Settings => Transition::Push(Box::new(SettingsScreen::new(ctx))),
+            #[cfg(not(target_arch = "wasm32"))]
+            TitleAction::Quit => Transition::Quit,
+        }
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        clear_background(LIGHTGRAY);
+        draw_heading("Kanjiban", 200., 100, ctx.fonts);
+        self.menu.draw();
+    }
+}
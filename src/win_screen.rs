@@ -0,0 +1,270 @@
+// kanjiban
+// (C) 2025 by JoAn
+// Results shown on top of a solved level: moves, pushes, time, best and
+// optimal score, with actions to go on to the next level, retry the level or
+// watch the solution again.
+
+use macroquad::prelude::*;
+
+use crate::app_state::{AppContext, Screen, Transition};
+use crate::game_logic::{Game, GameState};
+use crate::level_list::score_text;
+use crate::lurd::char_to_direction;
+use crate::menu::{Menu, draw_heading};
+use crate::play_screen::PlayScreen;
+use crate::save_data::LevelRecord;
+use crate::solver::{FRAME_SLICE, Solver, SolverLimits, SolverResult};
+
+// seconds until the next level starts, if auto-advance is on
+const AUTO_ADVANCE_DELAY: f64 = 5.;
+
+// How a level was solved, handed over by the play screen.
+pub struct Solution {
+    // index in the level list; None for test plays
+    pub level: Option<usize>,
+    pub start: GameState,
+    pub title: String,
+    pub author: String,
+    pub lurd: String,
+    pub moves: u64,
+    pub pushes: u64,
+    // seconds spent playing
+    pub time: f64,
+    // best score before this solution
+    pub previous_best: Option<LevelRecord>,
+    // moves made when the solver finished the level, if it did
+    pub assisted_from: Option<u64>,
+}
+
+impl Solution {
+    // A new game of the solved level, from its start.
+    pub fn new_game(&self) -> Game {
+        Game::new(self.start.clone(), self.title.clone(), self.author.clone())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum WinAction {
+    NextLevel,
+    BackToEditor,
+    TitleMenu,
+    Retry,
+    Replay,
+}
+
+pub struct WinScreen<'a> {
+    solution: Solution,
+    // where "next" leads; None if every level is solved
+    next_level: Option<usize>,
+    best: Option<LevelRecord>,
+    optimal_pushes: Option<u64>,
+    // search for the fewest pushes, a slice of it run every frame
+    optimal_search: Option<Solver>,
+    actions: Vec<(&'static str, WinAction)>,
+    menu: Menu<'a>,
+    // when the next level starts on its own
+    advance_at: Option<f64>,
+}
+
+// The level after the given one; from the last level on, the first one not solved yet.
+fn next_level(ctx: &AppContext, level: usize) -> Option<usize> {
+    if level + 1 < ctx.entries.len() {
+        return Some(level + 1);
+    }
+    ctx.entries
+        .iter()
+        .position(|entry| ctx.save_data.record(&entry.source.key()).is_none())
+}
+
+impl<'a> WinScreen<'a> {
+    pub fn new(ctx: &mut AppContext<'a>, solution: Solution) -> Self {
+        let next_level = solution.level.and_then(|level| next_level(ctx, level));
+        let first = match (solution.level, next_level) {
+            (None, _) => ("Back to editor", WinAction::BackToEditor),
+            (Some(_), Some(_)) => ("Next level", WinAction::NextLevel),
+            (Some(_), None) => ("Title menu", WinAction::TitleMenu),
+        };
+        let actions = vec![
+            first,
+            ("Retry", WinAction::Retry),
+            ("Replay solution", WinAction::Replay),
+        ];
+        let items = actions.iter().map(|(label, _)| label.to_string()).collect();
+        let best = solution
+            .level
+            .and_then(|level| ctx.save_data.record(&ctx.entries[level].source.key()))
+            .copied();
+        let advance_at = (ctx.settings.auto_advance && next_level.is_some())
+            .then(|| get_time() + AUTO_ADVANCE_DELAY);
+        // the fewest pushes are looked up, or searched for while the results
+        // are shown; larger levels stay unknown
+        let known = solution.level.and_then(|level| {
+            ctx.save_data
+                .optimal_pushes(&ctx.entries[level].source.key())
+        });
+        let (optimal_pushes, optimal_search) = match known {
+            Some(pushes) => (pushes, None),
+            None => (
+                None,
+                Some(Solver::optimal(&solution.start, SolverLimits::default())),
+            ),
+        };
+        Self {
+            optimal_pushes,
+            optimal_search,
+            solution,
+            next_level,
+            best,
+            actions,
+            menu: Menu::new(ctx.camera, 420., items, ctx.fonts),
+            advance_at,
+        }
+    }
+
+    // true iff this solution was recorded as better than the former best
+    fn is_new_best(&self) -> bool {
+        let score = LevelRecord {
+            moves: self.solution.moves,
+            pushes: self.solution.pushes,
+        };
+        self.solution.level.is_some()
+            && self.solution.assisted_from.is_none()
+            && self
+                .solution
+                .previous_best
+                .is_none_or(|best| score.is_better_than(&best))
+    }
+}
+
+impl<'a> Screen<'a> for WinScreen<'a> {
+    fn update(&mut self, ctx: &mut AppContext<'a>) -> Transition<'a> {
+        if let Some(search) = &mut self.optimal_search
+            && let Some(result) = search.run(FRAME_SLICE)
+        {
+            self.optimal_search = None;
+            self.optimal_pushes = match result {
+                SolverResult::Solved(moves) => {
+                    Some(moves.iter().filter(|m| m.pushed).count() as u64)
+                }
+                _ => None,
+            };
+            // an unknown result is kept as well, so the search isn't repeated
+            if let Some(level) = self.solution.level {
+                let key = ctx.entries[level].source.key();
+                ctx.save_data.set_optimal_pushes(&key, self.optimal_pushes);
+                ctx.save_data.save();
+            }
+        }
+        let action = if is_key_pressed(KeyCode::Escape) {
+            Some(WinAction::TitleMenu)
+        } else if let Some(index) = self.menu.update() {
+            Some(self.actions[index].1)
+        } else if self.advance_at.is_some_and(|at| get_time() >= at) {
+            Some(WinAction::NextLevel)
+        } else {
+            None
+        };
+        // any other key, click or tap stops the countdown
+        if get_last_key_pressed().is_some()
+            || is_mouse_button_pressed(MouseButton::Left)
+            || !touches().is_empty()
+        {
+            self.advance_at = None;
+        }
+        match action {
+            Some(WinAction::NextLevel) => match self.next_level {
+                Some(level) => Transition::PlayLevel(level),
+                None => Transition::ToTitle,
+            },
+            // this screen and the test play
+            Some(WinAction::BackToEditor) => Transition::Pop(2),
+            Some(WinAction::TitleMenu) if self.solution.level.is_none() => Transition::Pop(2),
+            Some(WinAction::TitleMenu) => Transition::ToTitle,
+            Some(WinAction::Retry) => {
+                let play = PlayScreen::new(ctx, self.solution.new_game(), self.solution.level);
+                Transition::Replace(2, Box::new(play))
+            }
+            Some(WinAction::Replay) => {
+                let directions = self
+                    .solution
+                    .lurd
+                    .chars()
+                    .filter_map(char_to_direction)
+                    .collect();
+                let replay = PlayScreen::replay(ctx, &self.solution, directions);
+                Transition::Replace(2, Box::new(replay))
+            }
+            None => Transition::Stay,
+        }
+    }
+
+    fn draw(&self, ctx: &AppContext<'a>) {
+        draw_rectangle(0., 0., 1280., 720., Color::new(0., 0., 0., 0.5));
+        draw_rectangle(340., 90., 600., 600., LIGHTGRAY);
+        draw_heading("Level solved!", 160., 60, ctx.fonts);
+        let solution = &self.solution;
+        let time = solution.time as u64;
+        let mut lines = vec![
+            (
+                format!("Moves: {}   Pushes: {}", solution.moves, solution.pushes),
+                DARKGRAY,
+            ),
+            (format!("Time: {}:{:02}", time / 60, time % 60), DARKGRAY),
+        ];
+        if solution.assisted_from.is_some() {
+            lines.push(("Finished by the solver, not recorded".to_owned(), DARKGRAY));
+        }
+        if let Some(best) = &self.best {
+            let new_best = if self.is_new_best() {
+                "  New best!"
+            } else {
+                ""
+            };
+            let color = if new_best.is_empty() {
+                DARKGRAY
+            } else {
+                DARKGREEN
+            };
+            lines.push((format!("Best: {}{}", score_text(best), new_best), color));
+        }
+        let optimal = match self.optimal_pushes {
+            Some(pushes) => format!("Optimal: {pushes} pushes"),
+            None if self.optimal_search.is_some() => "Optimal: searching...".to_owned(),
+            None => "Optimal: unknown".to_owned(),
+        };
+        lines.push((optimal, DARKGRAY));
+        for (index, (line, color)) in lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                380.,
+                215. + index as f32 * 40.,
+                TextParams {
+                    font: Some(ctx.fonts.font()),
+                    font_size: 30,
+                    color: *color,
+                    ..Default::default()
+                },
+            );
+        }
+        if let Some(at) = self.advance_at {
+            let seconds = (at - get_time()).max(0.).ceil();
+            let countdown = format!("Next level in {seconds} s");
+            draw_text_ex(
+                &countdown,
+                380.,
+                675.,
+                TextParams {
+                    font: Some(ctx.fonts.font()),
+                    font_size: 24,
+                    color: BLUE,
+                    ..Default::default()
+                },
+            );
+        }
+        self.menu.draw();
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,45 @@
+// kanjiban
+// (C) 2025 by JoAn
+// miniquad plugin giving the game access to the browser's localStorage,
+// used to keep the progress (see src/save_data.rs). Load it after
+// mq_js_bundle.js and before calling load().
+
+function kanjiban_storage_string(ptr, len) {
+    return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+}
+
+function kanjiban_storage_item(key_ptr, key_len) {
+    try {
+        return localStorage.getItem(kanjiban_storage_string(key_ptr, key_len));
+    } catch (e) {
+        // storage disabled, e.g. in private mode
+        return null;
+    }
+}
+
+miniquad_add_plugin({
+    name: "kanjiban_storage",
+    version: "0.1.0",
+    register_plugin: function (importObject) {
+        importObject.env.kanjiban_storage_set = function (key_ptr, key_len, value_ptr, value_len) {
+            try {
+                localStorage.setItem(
+                    kanjiban_storage_string(key_ptr, key_len),
+                    kanjiban_storage_string(value_ptr, value_len)
+                );
+            } catch (e) {
+                console.warn("Can't save progress: " + e);
+            }
+        };
+        importObject.env.kanjiban_storage_len = function (key_ptr, key_len) {
+            const value = kanjiban_storage_item(key_ptr, key_len);
+            return value === null ? -1 : new TextEncoder().encode(value).length;
+        };
+        importObject.env.kanjiban_storage_get = function (key_ptr, key_len, buffer_ptr, buffer_len) {
+            const value = kanjiban_storage_item(key_ptr, key_len);
+            const bytes = new TextEncoder().encode(value === null ? "" : value);
+            new Uint8Array(wasm_memory.buffer, buffer_ptr, buffer_len)
+                .set(bytes.subarray(0, buffer_len));
+        };
+    },
+});